@@ -0,0 +1,136 @@
+use crate::profile_parser::Profile;
+use crate::NodeId;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Mapping of identifiers to node names
+#[derive(Debug, Clone, Default)]
+pub struct Aliases {
+    /// lowercase identifier -> names
+    names: HashMap<String, Vec<String>>,
+}
+
+/// Identifiers that were renamed, not found or mapped to several names
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AliasReport {
+    pub mapped: BTreeMap<String, String>,
+    pub unmapped: BTreeSet<String>,
+    pub ambiguous: BTreeMap<String, Vec<String>>,
+}
+impl AliasReport {
+    pub fn print(&self) {
+        println!("- Mapped identifiers: {}", self.mapped.len());
+        for (id, name) in &self.mapped {
+            println!("  - {id} -> {name}");
+        }
+        println!("- Unmapped identifiers: {}", self.unmapped.len());
+        for id in &self.unmapped {
+            println!("  - {id}");
+        }
+        println!("- Ambiguous identifiers: {}", self.ambiguous.len());
+        for (id, names) in &self.ambiguous {
+            println!("  - {id}: {}", names.join(", "));
+        }
+    }
+}
+
+/// Reads a mapping file.
+///
+/// Each line maps an identifier to a node name, the two columns are separated by whitespace or a comma.
+/// Several identifiers can map to the same name. Empty lines and lines starting with `#` are ignored.
+pub fn read(file: &File) -> Result<Aliases> {
+    let mut aliases = Aliases::default();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns: Vec<&str> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|c| !c.is_empty())
+            .collect();
+        match columns.as_slice() {
+            [id, name] => {
+                aliases.add(id, name);
+                // a node name also maps to itself
+                aliases.add(name, name);
+            }
+            _ => bail!(
+                "line {}: expected an identifier and a node name, found '{line}'",
+                i + 1
+            ),
+        }
+    }
+    Ok(aliases)
+}
+
+impl Aliases {
+    fn add(&mut self, id: &str, name: &str) {
+        let names = self.names.entry(id.to_lowercase()).or_default();
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    /// Returns the node name of the identifier, the identifiers are matched case-insensitively.
+    /// Unmapped and ambiguous identifiers keep their name, ambiguous identifiers are recorded in
+    /// the report.
+    pub fn map(&self, id: &str, report: &mut AliasReport) -> String {
+        match self.names.get(&id.to_lowercase()).map(|n| n.as_slice()) {
+            Some([name]) => {
+                if name != id {
+                    report.mapped.insert(id.to_string(), name.clone());
+                }
+                name.clone()
+            }
+            Some(names) => {
+                report.ambiguous.insert(id.to_string(), names.to_vec());
+                id.to_string()
+            }
+            None => id.to_string(),
+        }
+    }
+
+    /// Returns the profile with the node names of all observed nodes, inputs, MIN and MAX.
+    /// Identifiers that match none of the network `nodes` after mapping are recorded as unmapped.
+    pub fn map_profile(
+        &self,
+        profile: &Profile,
+        nodes: &[NodeId],
+        report: &mut AliasReport,
+    ) -> Profile {
+        let nodes: BTreeSet<&NodeId> = nodes.iter().collect();
+        let mut map_node = |node: &NodeId| match node {
+            NodeId::Or(id) => {
+                let mapped = NodeId::Or(self.map(id, report));
+                if !nodes.contains(&mapped) {
+                    report.unmapped.insert(id.clone());
+                }
+                mapped
+            }
+            NodeId::And(id) => NodeId::And(id.clone()),
+        };
+        let mut mapped = profile.clone();
+        for node in mapped
+            .inputs
+            .iter_mut()
+            .chain(&mut mapped.min)
+            .chain(&mut mapped.max)
+        {
+            *node = map_node(node);
+        }
+        for observation in mapped.observations.iter_mut().chain(
+            mapped
+                .time_points
+                .iter_mut()
+                .flat_map(|t| &mut t.observations),
+        ) {
+            observation.node = map_node(&observation.node);
+        }
+        mapped
+    }
+}
@@ -3,16 +3,40 @@ use clap::Parser;
 use clingo::FactBase;
 use log::{error, info, warn};
 use serde::Serialize;
+use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use stderrlog;
 
-use iggy::cif_parser;
-use iggy::cif_parser::Graph;
+use iggy::aliases;
+use iggy::aliases::{AliasReport, Aliases};
+use iggy::cif_parser::{Compression, Graph};
+use iggy::midas_parser;
 use iggy::profile_parser;
 
 use iggy::profile_parser::{Behavior, Observation, Profile};
 
+use iggy::components::{
+    get_mcos_labelings_split, get_mcos_split, get_predictions_under_mcos_split,
+    get_predictions_under_scenfit_split, get_scenfit_labelings_split, get_scenfit_split, split,
+    Labeling,
+};
+use iggy::crossvalidation::cross_validate;
+use iggy::design::{rank_measurements, MeasurementScore};
+use iggy::diff::compare_networks;
+use iggy::evaluation::evaluate_predictions;
+use iggy::export::{to_cytoscape_json, to_graphml, Annotations};
+use iggy::html::HtmlReport;
+use iggy::inputs;
+use iggy::inputs::{check_inputs, InputSpec};
+use iggy::lint::{lint, LintIssue, Severity};
+use iggy::perturbation::{perturb, Intervention};
+use iggy::pruning::prune;
+use iggy::replicates::{merge_replicates, MergePolicy};
+use iggy::timeseries::{
+    get_time_series_labelings, get_time_series_predictions, TimePointLabels, TimeSeriesLabeling,
+};
 use iggy::CheckResult::Inconsistent;
 use iggy::*;
 
@@ -24,11 +48,12 @@ use iggy::*;
 #[derive(Parser, Debug)]
 #[clap(version, author)]
 struct Opt {
-    /// Influence graph in CIF format
+    /// Influence graph in CIF format, SBML-qual format for files ending in .sbml or .xml, SIF
+    /// format for files ending in .sif, or BoolNet format for files ending in .bnet
     #[clap(short = 'n', long = "network", value_name = "FILE", parse(from_os_str))]
     network_file: PathBuf,
 
-    /// Observations in bioquali format
+    /// Observations in bioquali format, or MIDAS format for files ending in .csv
     #[clap(
         short = 'o',
         long = "observations",
@@ -37,6 +62,31 @@ struct Opt {
     )]
     observations_file: Option<PathBuf>,
 
+    /// Treatment of a MIDAS file, required if the file contains several treatments
+    #[clap(long, value_name = "NAME", requires = "observations_file")]
+    treatment: Option<String>,
+
+    /// Minimal difference to the control for a MIDAS readout to count as increase or decrease
+    #[clap(long, value_name = "DIFF", default_value = "0.1")]
+    midas_threshold: f64,
+
+    /// Replicate of the observations, merged with the observations
+    #[clap(
+        long = "replicate",
+        value_name = "FILE",
+        parse(from_os_str),
+        requires = "observations_file"
+    )]
+    replicate_files: Vec<PathBuf>,
+
+    /// Mapping of identifiers to node names, applied to networks and observations
+    #[clap(long = "aliases", value_name = "FILE", parse(from_os_str))]
+    alias_file: Option<PathBuf>,
+
+    /// Policy for disagreeing replicates: majority, downgrade or drop
+    #[clap(long, value_name = "POLICY", default_value = "majority")]
+    merge_policy: MergePolicy,
+
     /// Disable forward propagation constraints
     #[clap(long, conflicts_with = "depmat")]
     fwd_propagation_off: bool,
@@ -53,14 +103,52 @@ struct Opt {
     #[clap(long)]
     depmat: bool,
 
+    /// Logic program with additional constraints for all queries
+    #[clap(long = "constraints", value_name = "FILE", parse(from_os_str))]
+    constraint_files: Vec<PathBuf>,
+
+    /// Additional constraint for all queries,
+    /// e.g. ':- vlabel(E,or("mtor"),-1), vlabel(E,or("s6k"),1).'
+    #[clap(long = "constraint", value_name = "RULE")]
+    constraints: Vec<String>,
+
+    /// Check the network for structural problems like self-loops and exit
+    #[clap(long)]
+    lint: bool,
+
     /// Compute minimal inconsistent cores
     #[clap(long)]
     mics: bool,
 
+    /// Compute at most N minimal inconsistent cores, 0=all
+    #[clap(long, value_name = "N", default_value = "0", requires = "mics")]
+    mics_limit: usize,
+
+    /// Stop the computation of minimal inconsistent cores after SECONDS
+    #[clap(long, value_name = "SECONDS", requires = "mics")]
+    mics_timeout: Option<u64>,
+
+    /// Merge linear chains of unobserved nodes and remove unobserved dead ends before solving
+    #[clap(long)]
+    compress: bool,
+
+    /// Remove nodes that cannot affect the observations before solving
+    #[clap(long)]
+    prune: bool,
+
+    /// Solve the independent components of the network in parallel
+    #[clap(long)]
+    split: bool,
+
     /// Declare nodes with indegree 0 as inputs
     #[clap(short = 'a', long)]
     auto_inputs: bool,
 
+    /// Inputs of all experiments, one node per line, merged with the computed inputs, a line !NODE
+    /// removes NODE from the computed inputs
+    #[clap(long = "inputs", value_name = "FILE", parse(from_os_str))]
+    inputs_file: Option<PathBuf>,
+
     /// Compute scenfit of the data, default is mcos
     #[clap(long)]
     scenfit: bool,
@@ -73,9 +161,47 @@ struct Opt {
     #[clap(short = 'p', long)]
     show_predictions: bool,
 
+    /// Evaluate the predictions against reference observations in bioquali format
+    #[clap(long = "evaluate", value_name = "FILE", parse(from_os_str))]
+    reference_file: Option<PathBuf>,
+
+    /// K-fold cross-validation of the observations, 0=leave-one-out
+    #[clap(long = "cross-validate", value_name = "K")]
+    folds: Option<usize>,
+
+    /// Rank unobserved nodes by the additional predictions measuring them would give,
+    /// K is the number of nodes that can be measured
+    #[clap(long = "design", value_name = "K")]
+    design: Option<usize>,
+
+    /// Compare consistency and predictions with a second version of the network
+    #[clap(long = "diff", value_name = "FILE", parse(from_os_str))]
+    diff_network_file: Option<PathBuf>,
+
+    /// Simulate interventions and report the changed predictions,
+    /// e.g. 'fix:mtor=-', 'remove:mtor', 'cut:pi3k->akt' or 'input:egf'
+    #[clap(long = "perturb", value_name = "INTERVENTION")]
+    interventions: Vec<Intervention>,
+
+    /// Print solver statistics and report the solving progress
+    #[clap(long)]
+    stats: bool,
+
     /// Print JSON output
     #[clap(long)]
     json: bool,
+
+    /// Write a self-contained HTML report with a drawing of the network
+    #[clap(long = "html", value_name = "FILE", parse(from_os_str))]
+    html_file: Option<PathBuf>,
+
+    /// Write the network with observations, inputs, predictions and MICs in GraphML format
+    #[clap(long = "graphml", value_name = "FILE", parse(from_os_str))]
+    graphml_file: Option<PathBuf>,
+
+    /// Write the network with observations, inputs, predictions and MICs in Cytoscape.js JSON format
+    #[clap(long = "cytoscape", value_name = "FILE", parse(from_os_str))]
+    cytoscape_file: Option<PathBuf>,
 }
 
 fn main() {
@@ -96,7 +222,14 @@ fn run() -> Result<()> {
     } else {
         println!("# Iggy Report");
     }
-    let setting = get_setting(&opt);
+    let mut report = opt
+        .html_file
+        .as_ref()
+        .map(|_| HtmlReport::new("Iggy Report"));
+    let setting = get_setting(&opt)?;
+    let settings: serde_json::Value = serde_json::from_str(&setting.to_json())?;
+    add_section(&mut report, "Iggy settings", &settings)?;
+    let statistics = opt.stats.then(StatisticsCollector::register);
 
     info!("Reading network model ...");
     if opt.json {
@@ -104,11 +237,59 @@ fn run() -> Result<()> {
     } else {
         println!("\nNetwork file: {}", opt.network_file.display());
     }
-    let f = File::open(&opt.network_file)
-        .context(format!("unable to open '{}'", opt.network_file.display()))?;
-
-    let ggraph = cif_parser::read(&f)
-        .context(format!("unable to parse '{}'", opt.network_file.display()))?;
+    add_section(&mut report, "Network file", &opt.network_file)?;
+    if opt.lint {
+        if NetworkFormat::of(&opt.network_file) != NetworkFormat::Cif {
+            Err(anyhow!(
+                "--lint checks networks in CIF format, '{}' is not a CIF file.",
+                opt.network_file.display()
+            ))?;
+        }
+        info!("Checking network ...");
+        let f = File::open(&opt.network_file)
+            .context(format!("unable to open '{}'", opt.network_file.display()))?;
+        let issues = lint(&f)?;
+        if opt.json {
+            let serialized = serde_json::to_string(&issues)?;
+            println!(",\"Network issues\":{serialized}");
+            print!("}}");
+        } else {
+            print_issues(&issues);
+        }
+        add_section(&mut report, "Network issues", &issues)?;
+        write_html(&report, &opt.html_file)?;
+        let errors = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count();
+        if errors > 0 {
+            Err(anyhow!("The network contains {errors} errors."))?;
+        }
+        return Ok(());
+    }
+    let aliases = match &opt.alias_file {
+        Some(alias_file) => {
+            info!("Reading aliases ...");
+            let f = File::open(alias_file)
+                .context(format!("unable to open '{}'", alias_file.display()))?;
+            let aliases =
+                aliases::read(&f).context(format!("unable to parse '{}'", alias_file.display()))?;
+            Some(aliases)
+        }
+        None => None,
+    };
+    let mut network_aliases = AliasReport::default();
+    let ggraph = read_network(&opt.network_file, &aliases, &mut network_aliases)?;
+    if aliases.is_some() {
+        if opt.json {
+            let serialized = serde_json::to_string(&network_aliases)?;
+            println!(",\"Network aliases\":{serialized}");
+        } else {
+            println!("\n## Network aliases\n");
+            network_aliases.print();
+        }
+        add_section(&mut report, "Network aliases", &network_aliases)?;
+    }
     let graph = ggraph.to_facts();
     let network_statistics = ggraph.statistics();
     if opt.json {
@@ -117,8 +298,9 @@ fn run() -> Result<()> {
     } else {
         network_statistics.print();
     }
+    add_section(&mut report, "Network statistics", &network_statistics)?;
 
-    let profile = {
+    let (pprofile, profile) = {
         if let Some(observationfile) = &opt.observations_file {
             info!("Reading observations ...");
             if opt.json {
@@ -126,11 +308,56 @@ fn run() -> Result<()> {
             } else {
                 println!("\nObservation file: {}", observationfile.display());
             }
-            let f = File::open(&observationfile)
-                .context(format!("unable to open '{}'", observationfile.display()))?;
-            let pprofile = profile_parser::read(&f, "x1")
-                .context(format!("unable to parse '{}'", observationfile.display()))?;
+            add_section(&mut report, "Observation file", observationfile)?;
+            let mut observation_aliases = AliasReport::default();
+            let pprofile = read_profile(
+                observationfile,
+                &opt,
+                &aliases,
+                ggraph.or_nodes(),
+                &mut observation_aliases,
+            )?;
+            let pprofile = if opt.replicate_files.is_empty() {
+                pprofile
+            } else {
+                let mut replicates = vec![pprofile];
+                for file in &opt.replicate_files {
+                    let replicate = read_profile(
+                        file,
+                        &opt,
+                        &aliases,
+                        ggraph.or_nodes(),
+                        &mut observation_aliases,
+                    )?;
+                    replicates.push(replicate);
+                }
+                info!("Merging replicates ...");
+                let (merged, merging) = merge_replicates(&replicates, "x1", opt.merge_policy)?;
+                if opt.json {
+                    println!(",\"Replicate files\":{:?}", opt.replicate_files);
+                    let serialized = serde_json::to_string(&merging)?;
+                    println!(",\"Replicate merging\":{serialized}");
+                } else {
+                    for file in &opt.replicate_files {
+                        println!("Replicate file: {}", file.display());
+                    }
+                    merging.print();
+                }
+                add_section(&mut report, "Replicate files", &opt.replicate_files)?;
+                add_section(&mut report, "Replicate merging", &merging)?;
+                merged
+            };
 
+            if aliases.is_some() {
+                if opt.json {
+                    let serialized = serde_json::to_string(&observation_aliases)?;
+                    println!(",\"Observation aliases\":{serialized}");
+                } else {
+                    println!("\n## Observation aliases\n");
+                    observation_aliases.print();
+                }
+                add_section(&mut report, "Observation aliases", &observation_aliases)?;
+            }
             let observations_statistics = observations_statistics(&pprofile, &ggraph);
             if opt.json {
                 let serialized = serde_json::to_string(&observations_statistics)?;
@@ -138,6 +365,11 @@ fn run() -> Result<()> {
             } else {
                 observations_statistics.print();
             }
+            add_section(
+                &mut report,
+                "Observations statistics",
+                &observations_statistics,
+            )?;
             let profile = pprofile.to_facts();
 
             info!("Checking observations ...");
@@ -149,10 +381,21 @@ fn run() -> Result<()> {
                     reasons.join("\n- ")
                 ))?;
             }
-            profile
+            if pprofile.is_time_series()
+                && (opt.mics
+                    || opt.reference_file.is_some()
+                    || opt.design.is_some()
+                    || opt.folds.is_some()
+                    || !opt.interventions.is_empty())
+            {
+                Err(anyhow!(
+                    "--mics, --evaluate, --design, --cross-validate and --perturb do not support time series."
+                ))?;
+            }
+            (Some(pprofile), profile)
         } else {
             warn!("Empty observation data.");
-            FactBase::new()
+            (None, FactBase::new())
         }
     };
 
@@ -164,12 +407,94 @@ fn run() -> Result<()> {
             FactBase::new()
         }
     };
+    let input_spec = match &opt.inputs_file {
+        Some(inputs_file) => {
+            info!("Reading inputs ...");
+            let f = File::open(inputs_file)
+                .context(format!("unable to open '{}'", inputs_file.display()))?;
+            let rename = |id: &str| match &aliases {
+                Some(aliases) => aliases.map(id, &mut AliasReport::default()),
+                None => id.to_string(),
+            };
+            inputs::read_renamed(&f, rename)
+                .context(format!("unable to parse '{}'", inputs_file.display()))?
+        }
+        None => InputSpec::default(),
+    };
+    let new_inputs = input_spec.merge(&new_inputs)?;
+    let input_report = check_inputs(&ggraph, pprofile.as_slice(), &new_inputs, &input_spec)?;
+    if opt.inputs_file.is_some() || !input_report.conflicts.is_empty() {
+        if opt.json {
+            let serialized = serde_json::to_string(&input_report)?;
+            println!(",\"Inputs\":{serialized}");
+        } else {
+            input_report.print();
+        }
+        add_section(&mut report, "Inputs", &input_report)?;
+    }
+
+    let pruned = if opt.prune {
+        info!("Pruning network ...");
+        let profiles: Vec<Profile> = pprofile.iter().cloned().collect();
+        let (pruned, pruning) = prune(&ggraph, &profiles, &new_inputs, opt.scenfit, &setting)?;
+        if opt.json {
+            let serialized = serde_json::to_string(&pruning)?;
+            println!(",\"Network pruning\":{serialized}");
+        } else {
+            pruning.print();
+        }
+        add_section(&mut report, "Network pruning", &pruning)?;
+        Some(pruned)
+    } else {
+        None
+    };
+    let sgraph = pruned.as_ref().unwrap_or(&ggraph);
+
+    let (cgraph, compression) = if opt.compress {
+        info!("Compressing network ...");
+        let mut keep = pprofile.as_ref().map(|p| p.nodes()).unwrap_or_default();
+        keep.extend(input_nodes(&new_inputs)?);
+        let (compressed, compression) = sgraph.compress(&keep);
+        if opt.json {
+            let serialized = serde_json::to_string(&compression)?;
+            println!(",\"Network compression\":{serialized}");
+        } else {
+            compression.print();
+        }
+        add_section(&mut report, "Network compression", &compression)?;
+        (compressed.to_facts(), Some(compression))
+    } else {
+        (sgraph.to_facts(), None)
+    };
+
+    let problems = if opt.split {
+        if setting.user_programs.is_empty() {
+            let problems = split(&cgraph, &profile, &new_inputs)?;
+            info!("Solving {} independent components ...", problems.len());
+            Some(problems)
+        } else {
+            warn!("User constraints may connect the components, solving the network as a whole.");
+            None
+        }
+    } else {
+        None
+    };
+
+    let time_series = pprofile.as_ref().filter(|p| p.is_time_series());
+    let export = opt.graphml_file.is_some() || opt.cytoscape_file.is_some();
+    // predictions and mics shown in the network drawing and the exported network
+    let mut network_predictions = vec![];
+    let mut network_mics = vec![];
+
     if !opt.json {
         println!("\n## Consistency results\n");
     }
     if opt.scenfit {
         info!("Computing scenfit of network and data ...");
-        let scenfit = get_scenfit(&graph, &profile, &new_inputs, &setting)?;
+        let scenfit = match &problems {
+            Some(problems) => get_scenfit_split(problems, &setting)?,
+            None => get_scenfit(&cgraph, &profile, &new_inputs, &setting)?,
+        };
 
         if scenfit == 0 {
             info!("The network and data are consistent");
@@ -178,6 +503,7 @@ fn run() -> Result<()> {
             } else {
                 println!("scenfit: 0");
             }
+            add_section(&mut report, "scenfit", &0)?;
         } else {
             info!("The network and data are inconsistent");
             if opt.json {
@@ -185,38 +511,83 @@ fn run() -> Result<()> {
             } else {
                 println!("scenfit: {scenfit}");
             }
+            add_section(&mut report, "scenfit", &scenfit)?;
             if opt.mics {
-                let mics = get_minimal_inconsistent_cores(&graph, &profile, &new_inputs, &setting)?;
-                if opt.json {
-                    print_json_mics(mics)?;
-                } else {
-                    print_mics(mics)?;
+                let mut mics =
+                    get_minimal_inconsistent_cores(&cgraph, &profile, &new_inputs, &setting)?;
+                if let Some(timeout) = opt.mics_timeout {
+                    mics = mics.with_timeout(Duration::from_secs(timeout));
                 }
+                let mics = if opt.json {
+                    print_json_mics(&mut mics, opt.mics_limit)?
+                } else {
+                    print_mics(&mut mics, opt.mics_limit)?
+                };
+                add_section(&mut report, "mics", &mics)?;
+                network_mics = mics;
             }
         }
-        if let Some(max_labelings) = opt.max_labelings {
-            let l = get_scenfit_labelings(&graph, &profile, &new_inputs, max_labelings, &setting)?;
-            if opt.json {
-                print_json_labelings(l)?;
+        if let (Some(max_labelings), None) = (opt.max_labelings, time_series) {
+            let l: Box<dyn Iterator<Item = Labeling>> = match &problems {
+                Some(problems) => Box::new(get_scenfit_labelings_split(
+                    problems,
+                    max_labelings,
+                    &setting,
+                )?),
+                None => Box::new(get_scenfit_labelings(
+                    &cgraph,
+                    &profile,
+                    &new_inputs,
+                    max_labelings,
+                    &setting,
+                )?),
+            };
+            let labelings = if opt.json {
+                print_json_labelings(l, &compression)?
             } else {
-                print_labelings(l)?;
-            }
+                print_labelings(l, &compression)?
+            };
+            add_section(&mut report, "labels under repair", &labelings)?;
         }
-        if opt.show_predictions {
+        if (opt.show_predictions || opt.reference_file.is_some() || export) && time_series.is_none()
+        {
             info!("Compute predictions ...");
-            let predictions =
-                get_predictions_under_scenfit(&graph, &profile, &new_inputs, &setting)?;
+            let predictions = match &problems {
+                Some(problems) => get_predictions_under_scenfit_split(problems, &setting)?,
+                None => get_predictions_under_scenfit(&cgraph, &profile, &new_inputs, &setting)?,
+            };
 
-            if opt.json {
-                let serialized = serde_json::to_string(&predictions)?;
-                println!(",\"Predictions\":{serialized}");
-            } else {
-                print_predictions(&predictions);
+            let predictions = match &compression {
+                Some(compression) => compression.expand_predictions(&predictions, setting.fp),
+                None => predictions,
+            };
+            if opt.show_predictions {
+                if opt.json {
+                    let serialized = serde_json::to_string(&predictions)?;
+                    println!(",\"Predictions\":{serialized}");
+                } else {
+                    print_predictions(&predictions);
+                }
+                add_section(&mut report, "Predictions", &predictions)?;
             }
+            if let Some(reference_file) = &opt.reference_file {
+                print_evaluation(
+                    &predictions,
+                    reference_file,
+                    &pprofile,
+                    &aliases,
+                    &opt,
+                    &mut report,
+                )?;
+            }
+            network_predictions = predictions;
         }
     } else {
         info!("Computing mcos of network and data ...");
-        let mcos = get_mcos(&graph, &profile, &new_inputs, &setting)?;
+        let mcos = match &problems {
+            Some(problems) => get_mcos_split(problems, &setting)?,
+            None => get_mcos(&cgraph, &profile, &new_inputs, &setting)?,
+        };
         if mcos == 0 {
             info!("The network and data are consistent");
             if opt.json {
@@ -224,6 +595,7 @@ fn run() -> Result<()> {
             } else {
                 println!("mcos: 0");
             }
+            add_section(&mut report, "mcos", &0)?;
         } else {
             info!("The network and data are inconsistent");
             if opt.json {
@@ -231,47 +603,271 @@ fn run() -> Result<()> {
             } else {
                 println!("mcos: {mcos}");
             }
+            add_section(&mut report, "mcos", &mcos)?;
             if opt.mics {
-                let mics = get_minimal_inconsistent_cores(&graph, &profile, &new_inputs, &setting)?;
+                let mut mics =
+                    get_minimal_inconsistent_cores(&cgraph, &profile, &new_inputs, &setting)?;
+                if let Some(timeout) = opt.mics_timeout {
+                    mics = mics.with_timeout(Duration::from_secs(timeout));
+                }
+                let mics = if opt.json {
+                    print_json_mics(&mut mics, opt.mics_limit)?
+                } else {
+                    print_mics(&mut mics, opt.mics_limit)?
+                };
+                add_section(&mut report, "mics", &mics)?;
+                network_mics = mics;
+            }
+        }
+        if let (Some(max_labelings), None) = (opt.max_labelings, time_series) {
+            let l: Box<dyn Iterator<Item = Labeling>> = match &problems {
+                Some(problems) => {
+                    Box::new(get_mcos_labelings_split(problems, max_labelings, &setting)?)
+                }
+                None => Box::new(get_mcos_labelings(
+                    &cgraph,
+                    &profile,
+                    &new_inputs,
+                    max_labelings,
+                    &setting,
+                )?),
+            };
+            let labelings = if opt.json {
+                print_json_labelings(l, &compression)?
+            } else {
+                print_labelings(l, &compression)?
+            };
+            add_section(&mut report, "labels under repair", &labelings)?;
+        }
+        if (opt.show_predictions || opt.reference_file.is_some() || export) && time_series.is_none()
+        {
+            info!("Compute predictions ...");
+            let predictions = match &problems {
+                Some(problems) => get_predictions_under_mcos_split(problems, &setting)?,
+                None => get_predictions_under_mcos(&cgraph, &profile, &new_inputs, &setting)?,
+            };
+            let predictions = match &compression {
+                Some(compression) => compression.expand_predictions(&predictions, setting.fp),
+                None => predictions,
+            };
+            if opt.show_predictions {
                 if opt.json {
-                    print_json_mics(mics)?;
+                    let serialized = serde_json::to_string(&predictions)?;
+                    println!(",\"Predictions\":{serialized}");
                 } else {
-                    print_mics(mics)?;
+                    print_predictions(&predictions);
                 }
+                add_section(&mut report, "Predictions", &predictions)?;
             }
+            if let Some(reference_file) = &opt.reference_file {
+                print_evaluation(
+                    &predictions,
+                    reference_file,
+                    &pprofile,
+                    &aliases,
+                    &opt,
+                    &mut report,
+                )?;
+            }
+            network_predictions = predictions;
         }
+    }
+    if let Some(series) = time_series {
         if let Some(max_labelings) = opt.max_labelings {
-            let l = get_mcos_labelings(&graph, &profile, &new_inputs, max_labelings, &setting)?;
+            let labelings = get_time_series_labelings(
+                &cgraph,
+                series,
+                &new_inputs,
+                max_labelings,
+                opt.scenfit,
+                &setting,
+            )?;
+            let labelings: Vec<TimeSeriesLabeling> = labelings
+                .into_iter()
+                .map(|l| TimeSeriesLabeling {
+                    time_points: expand_time_points(l.time_points, &compression, true),
+                    repairs: match &compression {
+                        Some(compression) => l
+                            .repairs
+                            .into_iter()
+                            .map(|r| compression.expand_repair(r))
+                            .collect(),
+                        None => l.repairs,
+                    },
+                })
+                .collect();
             if opt.json {
-                print_json_labelings(l)?;
+                let serialized = serde_json::to_string(&labelings)?;
+                println!(",\"labels under repair\":{serialized}");
             } else {
-                print_labelings(l)?;
+                for (count, labeling) in labelings.iter().enumerate() {
+                    println!();
+                    println!("- Labeling {}:", count + 1);
+                    labeling.print();
+                }
             }
+            add_section(&mut report, "labels under repair", &labelings)?;
         }
         if opt.show_predictions {
             info!("Compute predictions ...");
-            let predictions = get_predictions_under_mcos(&graph, &profile, &new_inputs, &setting)?;
+            let predictions =
+                get_time_series_predictions(&cgraph, series, &new_inputs, opt.scenfit, &setting)?;
+            let predictions = expand_time_points(predictions, &compression, setting.fp);
             if opt.json {
                 let serialized = serde_json::to_string(&predictions)?;
                 println!(",\"Predictions\":{serialized}");
             } else {
-                print_predictions(&predictions);
+                for time_point in &predictions {
+                    println!("\n# Time point {}", time_point.time_point);
+                    print_predictions(&time_point.labels);
+                }
+            }
+            add_section(&mut report, "Predictions", &predictions)?;
+        }
+    }
+    if let Some(diff_network_file) = &opt.diff_network_file {
+        info!("Reading second network model ...");
+        let second = read_network(diff_network_file, &aliases, &mut AliasReport::default())?;
+        let second_inputs = if opt.auto_inputs {
+            input_spec.merge(&guess_inputs(&second.to_facts())?)?
+        } else {
+            input_spec.merge(&FactBase::new())?
+        };
+        info!("Comparing networks ...");
+        let comparison = compare_networks(
+            &ggraph,
+            &new_inputs,
+            &second,
+            &second_inputs,
+            &profile,
+            opt.scenfit,
+            &setting,
+        )?;
+        if opt.json {
+            println!(",\"Second network file\":{:?}", diff_network_file);
+            let serialized = serde_json::to_string(&comparison)?;
+            println!(",\"Network comparison\":{serialized}");
+        } else {
+            println!("\n## Network comparison\n");
+            println!("Second network file: {}", diff_network_file.display());
+            comparison.print();
+        }
+        add_section(&mut report, "Second network file", diff_network_file)?;
+        add_section(&mut report, "Network comparison", &comparison)?;
+    }
+    if !opt.interventions.is_empty() {
+        info!("Simulating interventions ...");
+        let empty = Profile::new("x1");
+        let result = perturb(
+            &ggraph,
+            pprofile.as_ref().unwrap_or(&empty),
+            &new_inputs,
+            &opt.interventions,
+            opt.scenfit,
+            &setting,
+        )?;
+        if opt.json {
+            let serialized = serde_json::to_string(&result)?;
+            println!(",\"Perturbation\":{serialized}");
+        } else {
+            println!("\n## Perturbation");
+            result.print();
+        }
+        add_section(&mut report, "Perturbation", &result)?;
+    }
+    if let Some(folds) = opt.folds {
+        if let Some(pprofile) = &pprofile {
+            info!("Cross-validating network and data ...");
+            let validation =
+                cross_validate(&cgraph, pprofile, &new_inputs, folds, opt.scenfit, &setting)?;
+            if opt.json {
+                let serialized = serde_json::to_string(&validation)?;
+                println!(",\"Cross-validation\":{serialized}");
+            } else {
+                validation.print();
+            }
+            add_section(&mut report, "Cross-validation", &validation)?;
+        } else {
+            warn!("Cross-validation requires observations.");
+        }
+    }
+    if let Some(budget) = opt.design {
+        if let Some(pprofile) = &pprofile {
+            info!("Computing experimental design ...");
+            let ranking = rank_measurements(
+                &cgraph,
+                pprofile,
+                &new_inputs,
+                budget,
+                opt.scenfit,
+                &setting,
+            )?;
+            if opt.json {
+                let serialized = serde_json::to_string(&ranking)?;
+                println!(",\"Experimental design\":{serialized}");
+            } else {
+                print_design(&ranking, budget);
             }
+            add_section(&mut report, "Experimental design", &ranking)?;
+        } else {
+            warn!("Experimental design requires observations.");
         }
     }
+    if let Some(report) = &mut report {
+        report.add_network(&ggraph, &network_predictions)?;
+    }
+    if export {
+        let mut inputs = input_nodes(&new_inputs)?;
+        let mut observations = vec![];
+        if let Some(pprofile) = &pprofile {
+            inputs.extend(pprofile.inputs.iter().cloned());
+            observations = pprofile.observations.clone();
+        }
+        let annotations = Annotations {
+            observations,
+            inputs,
+            predictions: network_predictions,
+            mics: network_mics,
+            repairs: vec![],
+        };
+        export_network(
+            &ggraph,
+            &annotations,
+            &opt.graphml_file,
+            &opt.cytoscape_file,
+        )?;
+    }
+    if let Some(statistics) = &statistics {
+        statistics.print(opt.json)?;
+        add_section(&mut report, "Solver statistics", &statistics.statistics())?;
+    }
+    write_html(&report, &opt.html_file)?;
     if opt.json {
         print!("}}");
     }
     Ok(())
 }
 
-fn get_setting(opt: &Opt) -> Setting {
+fn get_setting(opt: &Opt) -> Result<Setting> {
+    let mut user_programs = vec![];
+    for file in &opt.constraint_files {
+        let program =
+            fs::read_to_string(file).context(format!("unable to read '{}'", file.display()))?;
+        user_programs.push(UserProgram::new(&file.display().to_string(), &program)?);
+    }
+    for (count, constraint) in opt.constraints.iter().enumerate() {
+        user_programs.push(UserProgram::new(
+            &format!("inline constraint {}", count + 1),
+            constraint,
+        )?);
+    }
     let setting = if opt.depmat {
         Setting {
             os: false,
             ep: true,
             fp: true,
             fc: true,
+            user_programs,
         }
     } else {
         Setting {
@@ -279,6 +875,7 @@ fn get_setting(opt: &Opt) -> Setting {
             ep: opt.elempath,
             fp: !opt.fwd_propagation_off,
             fc: !opt.founded_constraints_off,
+            user_programs,
         }
     };
     if opt.json {
@@ -286,7 +883,7 @@ fn get_setting(opt: &Opt) -> Setting {
     } else {
         print!("{setting}")
     }
-    setting
+    Ok(setting)
 }
 
 fn find_node_in_observations(observations: &[Observation], node_id: &NodeId) -> bool {
@@ -313,12 +910,14 @@ struct ObservationsStatistics {
     inputs: usize,       // number of inputs
     min: usize,          // number of MIN
     max: usize,          // number of MAX
+    time_points: usize,  // number of time points
     observations: usize, // total number of observations
     plus: usize,         // number of + observations
     minus: usize,        // number of - observations
     zero: usize,         // number of 0 observations
     not_plus: usize,     // number of NOT + observations
     not_minus: usize,    // number of NOT - observations
+    change: usize,       // number of CHANGE observations
 }
 impl ObservationsStatistics {
     fn print(&self) {
@@ -329,6 +928,9 @@ impl ObservationsStatistics {
         println!("- Inputs:                 {}", self.inputs);
         println!("- MIN:                    {}", self.min);
         println!("- MAX:                    {}", self.max);
+        if self.time_points > 0 {
+            println!("- Time points:            {}", self.time_points);
+        }
 
         println!("- Observations:           {}", self.observations);
         println!("  - +:                    {}", self.plus);
@@ -336,13 +938,15 @@ impl ObservationsStatistics {
         println!("  - 0:                    {}", self.zero);
         println!("  - notPlus:              {}", self.not_plus);
         println!("  - notMinus:             {}", self.not_minus);
+        println!("  - CHANGE:               {}", self.change);
     }
 }
 fn observations_statistics(profile: &Profile, graph: &Graph) -> ObservationsStatistics {
+    let observations: Vec<Observation> = profile.all_observations().cloned().collect();
     let model_nodes = graph.or_nodes();
     let mut unobserved = model_nodes.len();
     for node in model_nodes {
-        if find_node_in_observations(&profile.observations, node) {
+        if find_node_in_observations(&observations, node) {
             unobserved -= 1;
         }
     }
@@ -353,19 +957,20 @@ fn observations_statistics(profile: &Profile, graph: &Graph) -> ObservationsStat
     let mut zero = 0;
     let mut not_plus = 0;
     let mut not_minus = 0;
-    for obs in &profile.observations {
+    let mut change = 0;
+    for obs in &observations {
         match obs.behavior {
             Behavior::Plus => plus += 1,
             Behavior::Minus => minus += 1,
             Behavior::Zero => zero += 1,
             Behavior::NotPlus => not_plus += 1,
             Behavior::NotMinus => not_minus += 1,
-            Behavior::Change => panic!("Behavior Change not supported in observation"),
+            Behavior::Change => change += 1,
         }
     }
 
-    let mut not_in_model = profile.observations.len();
-    for obs in &profile.observations {
+    let mut not_in_model = observations.len();
+    for obs in &observations {
         if find_node_in_nodes(model_nodes, &obs.node) {
             not_in_model -= 1;
         }
@@ -377,67 +982,104 @@ fn observations_statistics(profile: &Profile, graph: &Graph) -> ObservationsStat
         inputs: profile.inputs.len(),
         min: profile.min.len(),
         max: profile.max.len(),
-        observations: profile.observations.len(),
+        time_points: profile.time_points.len(),
+        observations: observations.len(),
         plus,
         minus,
         zero,
         not_plus,
         not_minus,
+        change,
     }
 }
 
-fn print_mics(mics: Mics) -> Result<()> {
-    let mut oldmic = vec![];
-    for (count, mic) in mics.enumerate() {
-        if oldmic != *mic {
-            print!("- mic {}:\n  ", count + 1);
-            for e in mic.clone() {
-                let node = into_node_id(e)?;
-                print!("{node} ");
-            }
-            println!();
-            oldmic = mic;
+/// prints at most `limit` mics, 0=all, and returns them
+fn print_mics(mics: &mut Mics, limit: usize) -> Result<Vec<Vec<NodeId>>> {
+    let mut printed = vec![];
+    for (count, mic) in mics.by_ref().take(max_mics(limit)).enumerate() {
+        print!("- mic {}:\n  ", count + 1);
+        let mut nodes = vec![];
+        for e in mic {
+            let node = into_node_id(e)?;
+            print!("{node} ");
+            nodes.push(node);
         }
+        println!();
+        printed.push(nodes);
     }
-    Ok(())
+    if !mics.is_complete() {
+        println!("The search stopped at the limit or the timeout, there may be more mics.");
+    }
+    Ok(printed)
 }
-fn print_json_mics(mut mics: Mics) -> Result<()> {
+fn print_json_mics(mics: &mut Mics, limit: usize) -> Result<Vec<Vec<NodeId>>> {
+    let mut printed = vec![];
     println!(",\"mics\":[");
-
-    if let Some(mic) = mics.next() {
-        let nodes: Vec<NodeId> = mic.iter().map(|y| into_node_id(*y).unwrap()).collect();
+    for (count, mic) in mics.by_ref().take(max_mics(limit)).enumerate() {
+        let nodes: Vec<NodeId> = mic
+            .iter()
+            .map(|y| into_node_id(*y))
+            .collect::<Result<_>>()?;
         let serialized = serde_json::to_string(&nodes)?;
-        println!("{serialized}");
-        let mut oldmic = mic;
-
-        for mic in mics {
-            if oldmic != mic {
-                let nodes: Vec<NodeId> = mic.iter().map(|y| into_node_id(*y).unwrap()).collect();
-                let serialized = serde_json::to_string(&nodes)?;
-                println!(", {serialized}");
-                oldmic = mic;
-            }
+        if count == 0 {
+            println!("{serialized}");
+        } else {
+            println!(", {serialized}");
         }
+        printed.push(nodes);
     }
     println!("]");
-    Ok(())
+    println!(",\"mics complete\":{}", mics.is_complete());
+    Ok(printed)
+}
+fn max_mics(limit: usize) -> usize {
+    if limit == 0 {
+        usize::MAX
+    } else {
+        limit
+    }
+}
+
+/// A labeling together with its repairs
+#[derive(Serialize, Debug)]
+struct RepairedLabeling {
+    labels: Vec<Prediction>,
+    repairs: Vec<RepairOp>,
 }
 
-fn print_labelings(labelings: LabelsRepair) -> Result<()> {
-    for (count, (labels, repairs)) in labelings.enumerate() {
+/// prints the labelings and returns them
+fn print_labelings(
+    labelings: impl Iterator<Item = Labeling>,
+    compression: &Option<Compression>,
+) -> Result<Vec<RepairedLabeling>> {
+    let mut printed = vec![];
+    for (count, labeling) in labelings.enumerate() {
+        let (labels, repairs) = match compression {
+            Some(compression) => compression.expand_labeling(labeling),
+            None => labeling,
+        };
         println!();
         println!("- Labeling {}:", count + 1);
         print_labels(&labels);
 
         println!("\n  Repair set:");
-        for fix in repairs {
+        for fix in &repairs {
             println!("  - {fix}");
         }
+        printed.push(RepairedLabeling { labels, repairs });
     }
-    Ok(())
+    Ok(printed)
 }
-fn print_json_labelings(mut labelings: LabelsRepair) -> Result<()> {
+fn print_json_labelings(
+    labelings: impl Iterator<Item = Labeling>,
+    compression: &Option<Compression>,
+) -> Result<Vec<RepairedLabeling>> {
+    let mut printed = vec![];
     println!(",\"labels under repair\":[");
+    let mut labelings = labelings.map(|labeling| match compression {
+        Some(compression) => compression.expand_labeling(labeling),
+        None => labeling,
+    });
 
     if let Some((labels, repairs)) = labelings.next() {
         let serialized = serde_json::to_string(&labels)?;
@@ -446,6 +1088,7 @@ fn print_json_labelings(mut labelings: LabelsRepair) -> Result<()> {
         let serialized = serde_json::to_string(&repairs)?;
         println!(",\"repairs\":{serialized}");
         println!("}}");
+        printed.push(RepairedLabeling { labels, repairs });
 
         for (labels, repairs) in labelings {
             let serialized = serde_json::to_string(&labels)?;
@@ -454,10 +1097,28 @@ fn print_json_labelings(mut labelings: LabelsRepair) -> Result<()> {
             let serialized = serde_json::to_string(&repairs)?;
             println!(",\"repairs\":{serialized}");
             println!("}}");
+            printed.push(RepairedLabeling { labels, repairs });
         }
     }
     println!("]");
-    Ok(())
+    Ok(printed)
+}
+
+fn expand_time_points(
+    time_points: Vec<TimePointLabels>,
+    compression: &Option<Compression>,
+    fp: bool,
+) -> Vec<TimePointLabels> {
+    match compression {
+        Some(compression) => time_points
+            .into_iter()
+            .map(|t| TimePointLabels {
+                time_point: t.time_point,
+                labels: compression.expand_predictions(&t.labels, fp),
+            })
+            .collect(),
+        None => time_points,
+    }
 }
 
 fn print_labels(labels: &[Prediction]) {
@@ -493,3 +1154,160 @@ fn print_predictions(predictions: &[Prediction]) {
     println!("- predicted notMinus : {not_minus}");
     println!("- predicted CHANGE   : {change}");
 }
+
+/// reads observations and resolves the aliases of their nodes,
+/// identifiers that match none of the `nodes` are reported as unmapped
+fn read_profile(
+    observation_file: &Path,
+    opt: &Opt,
+    aliases: &Option<Aliases>,
+    nodes: &[NodeId],
+    report: &mut AliasReport,
+) -> Result<Profile> {
+    let f = File::open(observation_file)
+        .context(format!("unable to open '{}'", observation_file.display()))?;
+    let profile = if observation_file.extension().is_some_and(|e| e == "csv") {
+        let (midas, warnings) = midas_parser::read(&f, opt.midas_threshold)
+            .context(format!("unable to parse '{}'", observation_file.display()))?;
+        for warning in warnings {
+            warn!("{warning}");
+        }
+        let treatments = midas.treatments();
+        let index = match &opt.treatment {
+            Some(treatment) => treatments
+                .iter()
+                .position(|t| t == treatment)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unknown treatment '{treatment}' in '{}', expected one of: {}",
+                        observation_file.display(),
+                        treatments.join(", ")
+                    )
+                })?,
+            None if treatments.len() == 1 => 0,
+            None => {
+                return Err(anyhow!(
+                    "'{}' contains several treatments, select one with --treatment: {}",
+                    observation_file.display(),
+                    treatments.join(", ")
+                ))
+            }
+        };
+        midas.profile(index, "x1")
+    } else {
+        profile_parser::read(&f, "x1")
+            .context(format!("unable to parse '{}'", observation_file.display()))?
+    };
+    Ok(match aliases {
+        Some(aliases) => aliases.map_profile(&profile, nodes, report),
+        None => profile,
+    })
+}
+
+fn print_evaluation(
+    predictions: &[Prediction],
+    reference_file: &Path,
+    profile: &Option<Profile>,
+    aliases: &Option<Aliases>,
+    opt: &Opt,
+    report: &mut Option<HtmlReport>,
+) -> Result<()> {
+    info!("Evaluating predictions ...");
+    let json = opt.json;
+    let reference = read_profile(
+        reference_file,
+        opt,
+        aliases,
+        &[],
+        &mut AliasReport::default(),
+    )?;
+    // observed nodes would be trivially predicted
+    let observed: Vec<NodeId> = match profile {
+        Some(profile) => profile
+            .observations
+            .iter()
+            .map(|o| o.node.clone())
+            .collect(),
+        None => vec![],
+    };
+    let evaluation = evaluate_predictions(predictions, &reference, &observed);
+    if json {
+        println!(",\"Reference file\":{:?}", reference_file);
+        let serialized = serde_json::to_string(&evaluation)?;
+        println!(",\"Evaluation\":{serialized}");
+    } else {
+        println!("\nReference file: {}", reference_file.display());
+        evaluation.print();
+    }
+    add_section(report, "Reference file", reference_file)?;
+    add_section(report, "Evaluation", &evaluation)
+}
+
+/// adds a section to the HTML report if one is requested
+fn add_section<T: Serialize + ?Sized>(
+    report: &mut Option<HtmlReport>,
+    title: &str,
+    result: &T,
+) -> Result<()> {
+    match report {
+        Some(report) => report.add(title, result),
+        None => Ok(()),
+    }
+}
+
+fn write_html(report: &Option<HtmlReport>, html_file: &Option<PathBuf>) -> Result<()> {
+    if let (Some(report), Some(html_file)) = (report, html_file) {
+        info!("Writing HTML report ...");
+        fs::write(html_file, report.to_html())
+            .context(format!("unable to write '{}'", html_file.display()))?;
+    }
+    Ok(())
+}
+
+/// writes the annotated network in the requested formats
+fn export_network(
+    graph: &Graph,
+    annotations: &Annotations,
+    graphml_file: &Option<PathBuf>,
+    cytoscape_file: &Option<PathBuf>,
+) -> Result<()> {
+    if let Some(file) = graphml_file {
+        info!("Writing GraphML ...");
+        fs::write(file, to_graphml(graph, annotations)?)
+            .context(format!("unable to write '{}'", file.display()))?;
+    }
+    if let Some(file) = cytoscape_file {
+        info!("Writing Cytoscape.js JSON ...");
+        fs::write(file, to_cytoscape_json(graph, annotations)?)
+            .context(format!("unable to write '{}'", file.display()))?;
+    }
+    Ok(())
+}
+
+fn print_issues(issues: &[LintIssue]) {
+    println!("\n## Network check\n");
+    if issues.is_empty() {
+        println!("No issues found.");
+        return;
+    }
+    for issue in issues {
+        println!("- {issue}");
+    }
+    let errors = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+    println!(
+        "\nIssues: {} ({errors} errors, {} warnings)",
+        issues.len(),
+        issues.len() - errors
+    );
+}
+
+fn print_design(ranking: &[MeasurementScore], budget: usize) {
+    println!("\n## Experimental design\n");
+    println!("Measuring {budget} node(s):\n");
+    for score in ranking {
+        score.print();
+    }
+}
@@ -1,12 +1,20 @@
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use clingo::FactBase;
+use iggy::aliases;
+use iggy::aliases::AliasReport;
+use iggy::export::{to_cytoscape_json, to_graphml, to_sbml_qual, to_sif, Annotations};
+use iggy::html::HtmlReport;
+use iggy::inputs;
+use iggy::inputs::{check_inputs, InputSpec};
+use iggy::pruning::prune;
 use iggy::CheckResult::Inconsistent;
 use iggy::*;
 use log::{error, info, warn};
+use serde::Serialize;
 use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use stderrlog;
 use thiserror::Error;
@@ -18,11 +26,13 @@ use thiserror::Error;
 #[derive(Parser, Debug)]
 #[clap(name = "optgraph", version, author)]
 struct Opt {
-    /// Influence graph in CIF format
+    /// Influence graph in CIF format, SBML-qual format for files ending in .sbml or .xml, SIF
+    /// format for files ending in .sif, or BoolNet format for files ending in .bnet
     #[clap(short = 'n', long = "network", value_name = "FILE", parse(from_os_str))]
     network_file: PathBuf,
 
-    /// Directory of observations in bioquali format
+    /// Directory of observations in bioquali format or MIDAS format for files ending in .csv, or a
+    /// single MIDAS file
     #[clap(
         short = 'o',
         long = "observations",
@@ -31,6 +41,14 @@ struct Opt {
     )]
     observations_dir: PathBuf,
 
+    /// Minimal difference to the control for a MIDAS readout to count as increase or decrease
+    #[clap(long, value_name = "DIFF", default_value = "0.1")]
+    midas_threshold: f64,
+
+    /// Mapping of identifiers to node names, applied to the network and the observations
+    #[clap(long = "aliases", value_name = "FILE", parse(from_os_str))]
+    alias_file: Option<PathBuf>,
+
     /// Disable forward propagation constraints
     #[clap(long, conflicts_with = "depmat")]
     fwd_propagation_off: bool,
@@ -47,23 +65,92 @@ struct Opt {
     #[clap(long)]
     depmat: bool,
 
+    /// Logic program with additional constraints for all queries
+    #[clap(long = "constraints", value_name = "FILE", parse(from_os_str))]
+    constraint_files: Vec<PathBuf>,
+
+    /// Additional constraint for all queries,
+    /// e.g. ':- vlabel(E,or("mtor"),-1), vlabel(E,or("s6k"),1).'
+    #[clap(long = "constraint", value_name = "RULE")]
+    constraints: Vec<String>,
+
     /// Declare nodes with indegree 0 as inputs
     #[clap(short = 'a', long)]
     auto_inputs: bool,
 
+    /// Inputs of all experiments, one node per line, merged with the computed inputs, a line !NODE
+    /// removes NODE from the computed inputs
+    #[clap(long = "inputs", value_name = "FILE", parse(from_os_str))]
+    inputs_file: Option<PathBuf>,
+
+    /// Merge linear chains of unobserved nodes and remove unobserved dead ends before solving,
+    /// only for the repair mode remove
+    #[clap(long)]
+    compress: bool,
+
+    /// Remove nodes that cannot affect the observations before solving,
+    /// only for the repair mode remove
+    #[clap(long)]
+    prune: bool,
+
     /// Show N repairs, default is OFF, 0=all
     #[clap(short = 'r', long = "show-repairs", value_name = "N")]
     max_repairs: Option<u32>,
 
     /// REPAIR_MODE: remove = remove edges (default),
     ///              optgraph = add + remove edges,
-    ///              flip = flip direction of edges
+    ///              flip = flip direction of edges,
+    ///              sign = flip sign of edges,
+    ///              combined = all repair operations with --costs
     #[clap(short = 'm', long)]
     repair_mode: Option<RepairMode>,
 
+    /// Costs of the repair operations in the combined repair mode, e.g. 'remove=1,add=3'
+    /// [default: remove=1,flip=1,sign=1,add=2,influence=2,observation=2]
+    #[clap(long, value_name = "COSTS")]
+    costs: Option<RepairCosts>,
+
+    /// Method of the repair mode optgraph with --elempath or --depmat: greedy (fast, not
+    /// guaranteed optimal) or exact (networks with at most 10 nodes, greedy otherwise)
+    /// [default: greedy]
+    #[clap(long, value_name = "METHOD")]
+    method: Option<RepairMethod>,
+
+    /// Print solver statistics and report the solving progress
+    #[clap(long)]
+    stats: bool,
+
     /// Print JSON output
     #[clap(long)]
     json: bool,
+
+    /// Write a self-contained HTML report with a drawing of the network
+    #[clap(long = "html", value_name = "FILE", parse(from_os_str))]
+    html_file: Option<PathBuf>,
+
+    /// Write the network with observations, inputs and the first repair set in GraphML format
+    #[clap(long = "graphml", value_name = "FILE", parse(from_os_str))]
+    graphml_file: Option<PathBuf>,
+
+    /// Write the network with observations, inputs and the first repair set in Cytoscape.js JSON
+    /// format
+    #[clap(long = "cytoscape", value_name = "FILE", parse(from_os_str))]
+    cytoscape_file: Option<PathBuf>,
+
+    /// Write the network with the first repair set applied in SBML-qual format
+    #[clap(long = "sbml", value_name = "FILE", parse(from_os_str))]
+    sbml_file: Option<PathBuf>,
+
+    /// Write the network with the first repair set applied in SIF format
+    #[clap(long = "sif", value_name = "FILE", parse(from_os_str))]
+    sif_file: Option<PathBuf>,
+}
+
+/// A repair set of the combined repair mode with its cost
+#[derive(Serialize)]
+struct CostedRepairSet<'a> {
+    repairs: &'a [RepairOp],
+    cost: CostBreakdown,
 }
 
 #[derive(Debug)]
@@ -71,6 +158,8 @@ enum RepairMode {
     Remove,
     OptGraph,
     Flip,
+    Sign,
+    Combined,
 }
 #[derive(Debug, Error)]
 #[error("ParseRepairModeError: {msg}")]
@@ -89,8 +178,27 @@ impl FromStr for RepairMode {
             "remove" => Ok(RepairMode::Remove),
             "optgraph" => Ok(RepairMode::OptGraph),
             "flip" => Ok(RepairMode::Flip),
+            "sign" => Ok(RepairMode::Sign),
+            "combined" => Ok(RepairMode::Combined),
             _ => Err(ParseRepairModeError::new(
-                "failed to parse repair mode. Possible values are: add, optgraph and flip.",
+                "failed to parse repair mode. Possible values are: remove, optgraph, flip, sign and combined.",
+            )),
+        }
+    }
+}
+#[derive(Debug)]
+enum RepairMethod {
+    Greedy,
+    Exact,
+}
+impl FromStr for RepairMethod {
+    type Err = ParseRepairModeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "greedy" => Ok(RepairMethod::Greedy),
+            "exact" => Ok(RepairMethod::Exact),
+            _ => Err(ParseRepairModeError::new(
+                "failed to parse repair method. Possible values are: greedy and exact.",
             )),
         }
     }
@@ -113,7 +221,26 @@ fn run() -> Result<()> {
     } else {
         println!("# Optgraph Report");
     }
-    let setting = get_setting(&opt);
+    let mut report = opt
+        .html_file
+        .as_ref()
+        .map(|_| HtmlReport::new("Optgraph Report"));
+    let setting = get_setting(&opt)?;
+    let settings: serde_json::Value = serde_json::from_str(&setting.to_json())?;
+    add_section(&mut report, "Iggy settings", &settings)?;
+    let combined = matches!(opt.repair_mode, Some(RepairMode::Combined));
+    if opt.costs.is_some() && !combined {
+        Err(anyhow!("--costs requires the repair mode combined."))?;
+    }
+    let costs = opt.costs.clone().unwrap_or_default();
+    if opt.method.is_some()
+        && !(matches!(opt.repair_mode, Some(RepairMode::OptGraph)) && setting.ep)
+    {
+        Err(anyhow!(
+            "--method requires the repair mode optgraph with --elempath or --depmat."
+        ))?;
+    }
+    let statistics = opt.stats.then(StatisticsCollector::register);
 
     info!("Reading network model ...");
     if opt.json {
@@ -121,10 +248,30 @@ fn run() -> Result<()> {
     } else {
         println!("\nNetwork file: {}", opt.network_file.display());
     }
-    let f = File::open(&opt.network_file)
-        .context(format!("unable to open '{}'", opt.network_file.display()))?;
-    let ggraph = cif_parser::read(&f)
-        .context(format!("unable to parse '{}'", opt.network_file.display()))?;
+    add_section(&mut report, "Network file", &opt.network_file)?;
+    let aliases = match &opt.alias_file {
+        Some(alias_file) => {
+            info!("Reading aliases ...");
+            let f = File::open(alias_file)
+                .context(format!("unable to open '{}'", alias_file.display()))?;
+            let aliases =
+                aliases::read(&f).context(format!("unable to parse '{}'", alias_file.display()))?;
+            Some(aliases)
+        }
+        None => None,
+    };
+    let mut network_aliases = AliasReport::default();
+    let ggraph = read_network(&opt.network_file, &aliases, &mut network_aliases)?;
+    if aliases.is_some() {
+        if opt.json {
+            let serialized = serde_json::to_string(&network_aliases)?;
+            println!(",\"Network aliases\":{serialized}");
+        } else {
+            println!("\n## Network aliases\n");
+            network_aliases.print();
+        }
+        add_section(&mut report, "Network aliases", &network_aliases)?;
+    }
     let graph = ggraph.to_facts();
     let network_statistics = ggraph.statistics();
     if opt.json {
@@ -133,11 +280,20 @@ fn run() -> Result<()> {
     } else {
         network_statistics.print();
     }
+    add_section(&mut report, "Network statistics", &network_statistics)?;
 
-    let directory = fs::read_dir(&opt.observations_dir).context(format!(
-        "unable to read directory '{}'",
-        opt.observations_dir.display()
-    ))?;
+    // a single MIDAS file or a directory of observation files
+    let paths: Vec<PathBuf> = if opt.observations_dir.is_file() {
+        vec![opt.observations_dir.clone()]
+    } else {
+        let directory = fs::read_dir(&opt.observations_dir).context(format!(
+            "unable to read directory '{}'",
+            opt.observations_dir.display()
+        ))?;
+        directory
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?
+    };
     info!("Reading observations ...");
     let mut observation_files = vec![];
     if opt.json {
@@ -146,49 +302,71 @@ fn run() -> Result<()> {
         println!("\nObservation files:\n");
     }
     let mut profiles = Ok(FactBase::new());
-    for entry in directory {
-        let observationfile = entry?.path();
-        let name = format!("{}", observationfile.display());
+    let mut observed = vec![];
+    let mut pprofiles = vec![];
+    let mut observation_aliases = AliasReport::default();
+    for observationfile in paths {
+        let file_name = format!("{}", observationfile.display());
         if !opt.json {
-            println!("- {name}");
+            println!("- {file_name}");
         }
-        let f = File::open(&observationfile)?;
+        let file_profiles = read_profiles(&observationfile, opt.midas_threshold)?;
         observation_files.push(observationfile);
 
-        let pprofile =
-            profile_parser::read(&f, &name).context(format!("unable to parse '{}'", &name))?;
-        let profile = pprofile.to_facts();
-
-        if let Inconsistent(reasons) = check_observations(&profile)? {
-            match profiles {
-                Ok(_) => {
-                    warn!("Contradictory observations. Please correct them!");
-                    profiles = Err(anyhow!(
-                        "\nInconsistent observations in {name}\n- {}",
-                        reasons.join("\n- ")
-                    ));
+        for pprofile in file_profiles {
+            let name = pprofile.id().clone();
+            let pprofile = match &aliases {
+                Some(aliases) => {
+                    aliases.map_profile(&pprofile, ggraph.or_nodes(), &mut observation_aliases)
                 }
-                Err(ref e) => {
-                    warn!("Contradictory observations. Please correct them!");
-                    profiles = Err(anyhow!(
-                        "\nInconsistent observations in {name}\n- {}\n{}",
-                        reasons.join("\n- "),
-                        e
-                    ))
+                None => pprofile,
+            };
+            let profile = pprofile.to_facts();
+            observed.extend(pprofile.nodes());
+            pprofiles.push(pprofile);
+
+            if let Inconsistent(reasons) = check_observations(&profile)? {
+                match profiles {
+                    Ok(_) => {
+                        warn!("Contradictory observations. Please correct them!");
+                        profiles = Err(anyhow!(
+                            "\nInconsistent observations in {name}\n- {}",
+                            reasons.join("\n- ")
+                        ));
+                    }
+                    Err(ref e) => {
+                        warn!("Contradictory observations. Please correct them!");
+                        profiles = Err(anyhow!(
+                            "\nInconsistent observations in {name}\n- {}\n{}",
+                            reasons.join("\n- "),
+                            e
+                        ))
+                    }
                 }
             }
-        }
-        match profiles {
-            Ok(mut acc) => {
-                acc.union(&profile);
-                profiles = Ok(acc)
+            match profiles {
+                Ok(mut acc) => {
+                    acc.union(&profile);
+                    profiles = Ok(acc)
+                }
+                Err(e) => profiles = Err(e),
             }
-            Err(e) => profiles = Err(e),
         }
     }
     if opt.json {
         println!("{}", serde_json::to_string(&observation_files)?);
     }
+    add_section(&mut report, "Observation files", &observation_files)?;
+    if aliases.is_some() {
+        if opt.json {
+            let serialized = serde_json::to_string(&observation_aliases)?;
+            println!(",\"Observation aliases\":{serialized}");
+        } else {
+            println!("\n## Observation aliases\n");
+            observation_aliases.print();
+        }
+        add_section(&mut report, "Observation aliases", &observation_aliases)?;
+    }
     let profiles = profiles?;
 
     let new_inputs = {
@@ -199,36 +377,150 @@ fn run() -> Result<()> {
             FactBase::new()
         }
     };
+    let input_spec = match &opt.inputs_file {
+        Some(inputs_file) => {
+            info!("Reading inputs ...");
+            let f = File::open(inputs_file)
+                .context(format!("unable to open '{}'", inputs_file.display()))?;
+            let rename = |id: &str| match &aliases {
+                Some(aliases) => aliases.map(id, &mut AliasReport::default()),
+                None => id.to_string(),
+            };
+            inputs::read_renamed(&f, rename)
+                .context(format!("unable to parse '{}'", inputs_file.display()))?
+        }
+        None => InputSpec::default(),
+    };
+    let new_inputs = input_spec.merge(&new_inputs)?;
+    let input_report = check_inputs(&ggraph, &pprofiles, &new_inputs, &input_spec)?;
+    if opt.inputs_file.is_some() || !input_report.conflicts.is_empty() {
+        if opt.json {
+            let serialized = serde_json::to_string(&input_report)?;
+            println!(",\"Inputs\":{serialized}");
+        } else {
+            input_report.print();
+        }
+        add_section(&mut report, "Inputs", &input_report)?;
+    }
+
+    let pruned = if opt.prune {
+        if let Some(
+            RepairMode::OptGraph | RepairMode::Flip | RepairMode::Sign | RepairMode::Combined,
+        ) = opt.repair_mode
+        {
+            Err(anyhow!(
+                "Pruning is only supported for the repair mode remove."
+            ))?;
+        }
+        info!("Pruning network ...");
+        let (pruned, pruning) = prune(&ggraph, &pprofiles, &new_inputs, true, &setting)?;
+        if opt.json {
+            let serialized = serde_json::to_string(&pruning)?;
+            println!(",\"Network pruning\":{serialized}");
+        } else {
+            pruning.print();
+        }
+        add_section(&mut report, "Network pruning", &pruning)?;
+        Some(pruned)
+    } else {
+        None
+    };
+    let sgraph = pruned.as_ref().unwrap_or(&ggraph);
+
+    let (graph, compression) = if opt.compress {
+        if let Some(
+            RepairMode::OptGraph | RepairMode::Flip | RepairMode::Sign | RepairMode::Combined,
+        ) = opt.repair_mode
+        {
+            Err(anyhow!(
+                "Compression is only supported for the repair mode remove."
+            ))?;
+        }
+        info!("Compressing network ...");
+        observed.extend(input_nodes(&new_inputs)?);
+        let (compressed, compression) = sgraph.compress(&observed);
+        if opt.json {
+            let serialized = serde_json::to_string(&compression)?;
+            println!(",\"Network compression\":{serialized}");
+        } else {
+            compression.print();
+        }
+        add_section(&mut report, "Network compression", &compression)?;
+        (compressed.to_facts(), Some(compression))
+    } else {
+        (sgraph.to_facts(), None)
+    };
+
+    let exact = matches!(opt.method, Some(RepairMethod::Exact));
+    if exact && !supports_exact_elem_path(&graph)? {
+        warn!(
+            "The exact method is limited to networks with at most {MAX_EXACT_ELEM_PATH_NODES} nodes, \
+             using the greedy method instead."
+        );
+    }
+    let greedy = setting.ep && !(exact && supports_exact_elem_path(&graph)?);
 
     if !opt.json {
         println!("\n## Consistency results\n");
     }
     // compute opt scenfit repair scores
     let (scenfit, repair_score, redges) = match opt.repair_mode {
-        Some(RepairMode::OptGraph) if setting.ep => {
+        Some(RepairMode::OptGraph) if greedy => {
             info!("Computing repair through add/removing edges ... ");
             info!("using greedy method ... ");
             let (scenfit, repair_score, redges) =
-                get_opt_add_remove_edges_greedy(&graph, &profiles, &new_inputs)?;
+                get_opt_add_remove_edges_greedy(&graph, &profiles, &new_inputs, &setting)?;
             if opt.json {
                 println!(",\"scenfit\":{scenfit}");
                 println!(",\"repair score\":{repair_score}");
+                println!(",\"optimality proven\":false");
             } else {
                 println!("The network and data can reach a scenfit of {scenfit}.");
+                println!("The greedy method adds one edge at a time, the repairs are not proven to be optimal.");
+                if !exact {
+                    println!("Use --method exact to compute optimal repairs.");
+                }
             }
+            add_section(&mut report, "optimality proven", &false)?;
             (scenfit, repair_score, redges)
-            //   with {} removals and {} additions.", repairs, edges.len());
         }
-        Some(RepairMode::OptGraph) if !setting.ep => {
+        Some(RepairMode::OptGraph) => {
             info!("Computing repair through add/removing edges ... ");
             let (scenfit, repair_score) =
                 get_opt_add_remove_edges(&graph, &profiles, &new_inputs, &setting)?;
             if opt.json {
                 println!(",\"scenfit\":{scenfit}");
                 println!(",\"repair score\":{repair_score}");
+                println!(",\"optimality proven\":true");
             } else {
                 println!("The network and data can reach a scenfit of {scenfit} with repairs of score {repair_score}");
             }
+            add_section(&mut report, "optimality proven", &true)?;
+            (scenfit, repair_score, vec![])
+        }
+        Some(RepairMode::Combined) => {
+            info!("Computing repair through combined repair operations ... ");
+            let cost = get_opt_combined_repairs(&graph, &profiles, &new_inputs, &costs, &setting)?;
+            if opt.json {
+                println!(",\"Repair costs\":{}", serde_json::to_string(&costs)?);
+                println!(",\"repair cost\":{cost}");
+            } else {
+                println!("Repair costs: {costs}");
+                println!("The network and data can be made consistent with repairs of total cost {cost}.");
+            }
+            add_section(&mut report, "Repair costs", &costs)?;
+            (0, cost, vec![])
+        }
+        Some(RepairMode::Sign) => {
+            info!("Computing repair through flipping edge signs ... ");
+            let (scenfit, repair_score) =
+                get_opt_flip_edge_signs(&graph, &profiles, &new_inputs, &setting)?;
+            if opt.json {
+                println!(",\"scenfit\":{scenfit}");
+                println!(",\"repair score\":{repair_score}");
+            } else {
+                println!("The network and data can reach a scenfit of {scenfit} with {repair_score} flipped edge signs");
+            }
             (scenfit, repair_score, vec![])
         }
         Some(RepairMode::Flip) => {
@@ -259,31 +551,66 @@ fn run() -> Result<()> {
         }
     };
 
+    if combined {
+        add_section(&mut report, "repair cost", &repair_score)?;
+    } else {
+        add_section(&mut report, "scenfit", &scenfit)?;
+        add_section(&mut report, "repair score", &repair_score)?;
+    }
+
     // compute optimal repairs
+    let mut network_repairs = vec![];
     if repair_score > 0 {
         if let Some(max_repairs) = opt.max_repairs {
             let repairs = match opt.repair_mode {
-                Some(RepairMode::OptGraph) if setting.ep => {
-                    let mut repairs = vec![];
+                Some(RepairMode::OptGraph) if greedy => {
+                    let mut repairs: Vec<Vec<clingo::Symbol>> = vec![];
                     for new_edges in redges {
-                        //TODO return only max_repairs solutions
                         let removes = get_opt_repairs_add_remove_edges_greedy(
                             &graph,
                             &profiles,
                             &new_inputs,
-                            &new_edges,
-                            scenfit,
-                            repair_score,
+                            &GreedyEdges {
+                                edges: &new_edges,
+                                scenfit,
+                                repair_score,
+                            },
                             max_repairs,
+                            &setting,
                         )?;
 
-                        for i in removes {
-                            repairs.push(i);
+                        // the same repair set can be reached with different edges
+                        for mut repair in removes {
+                            repair.sort_by_key(|symbol| symbol.to_string());
+                            if !repairs.contains(&repair) {
+                                repairs.push(repair);
+                            }
                         }
                     }
+                    if max_repairs > 0 {
+                        repairs.truncate(max_repairs as usize);
+                    }
                     repairs
                 }
-                Some(RepairMode::OptGraph) if !setting.ep => get_opt_repairs_add_remove_edges(
+                Some(RepairMode::OptGraph) => get_opt_repairs_add_remove_edges(
+                    &graph,
+                    &profiles,
+                    &new_inputs,
+                    scenfit,
+                    repair_score,
+                    max_repairs,
+                    &setting,
+                )?,
+                Some(RepairMode::Combined) => get_opt_repairs_combined(
+                    &graph,
+                    &profiles,
+                    &new_inputs,
+                    &costs,
+                    repair_score,
+                    max_repairs,
+                    &setting,
+                )?,
+                Some(RepairMode::Sign) => get_opt_repairs_flip_edge_signs(
                     &graph,
                     &profiles,
                     &new_inputs,
@@ -312,42 +639,112 @@ fn run() -> Result<()> {
                 )?,
             };
 
-            if opt.json {
-                let repairs: Vec<Vec<RepairOp>> = repairs
+            let repairs: Vec<Vec<RepairOp>> = repairs
+                .iter()
+                .map(|set| {
+                    set.iter()
+                        .map(|symbol| {
+                            let repair_op = into_repair(symbol).unwrap();
+                            match &compression {
+                                Some(compression) => compression.expand_repair(repair_op),
+                                None => repair_op,
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            if combined {
+                let repairs: Vec<CostedRepairSet> = repairs
                     .iter()
-                    .map(|set| {
-                        set.iter()
-                            .map(|symbol| into_repair(symbol).unwrap())
-                            .collect()
+                    .map(|r| CostedRepairSet {
+                        repairs: r,
+                        cost: costs.breakdown(r),
                     })
                     .collect();
-
-                let serialized = serde_json::to_string(&repairs)?;
-                println!(",\"Repair sets\":{serialized}");
+                if opt.json {
+                    let serialized = serde_json::to_string(&repairs)?;
+                    println!(",\"Repair sets\":{serialized}");
+                } else {
+                    for (count, r) in repairs.iter().enumerate() {
+                        println!("\n- Repair set {}:", count + 1);
+                        for repair_op in r.repairs {
+                            println!("  - {repair_op}");
+                        }
+                        println!("  - cost: {}", r.cost);
+                    }
+                }
+                add_section(&mut report, "Repair sets", &repairs)?;
             } else {
-                for (count, r) in repairs.iter().enumerate() {
-                    println!("\n- Repair set {}:", count + 1);
-                    for e in r {
-                        let repair_op = into_repair(e)?;
-                        println!("  - {repair_op}");
+                if opt.json {
+                    let serialized = serde_json::to_string(&repairs)?;
+                    println!(",\"Repair sets\":{serialized}");
+                } else {
+                    for (count, r) in repairs.iter().enumerate() {
+                        println!("\n- Repair set {}:", count + 1);
+                        for repair_op in r {
+                            println!("  - {repair_op}");
+                        }
                     }
                 }
+                add_section(&mut report, "Repair sets", &repairs)?;
             }
+            network_repairs = repairs.into_iter().next().unwrap_or_default();
         }
     }
+    if let Some(report) = &mut report {
+        report.add_network(&ggraph, &[])?;
+    }
+    if opt.graphml_file.is_some() || opt.cytoscape_file.is_some() {
+        let annotations = network_annotations(&pprofiles, &new_inputs, &network_repairs)?;
+        export_network(
+            &ggraph,
+            &annotations,
+            &opt.graphml_file,
+            &opt.cytoscape_file,
+        )?;
+    }
+    if opt.sbml_file.is_some() || opt.sif_file.is_some() {
+        write_network(
+            &ggraph.repaired(&network_repairs),
+            &opt.sbml_file,
+            &opt.sif_file,
+        )?;
+    }
+    if let Some(statistics) = &statistics {
+        statistics.print(opt.json)?;
+        add_section(&mut report, "Solver statistics", &statistics.statistics())?;
+    }
+    if let (Some(report), Some(html_file)) = (&report, &opt.html_file) {
+        info!("Writing HTML report ...");
+        fs::write(html_file, report.to_html())
+            .context(format!("unable to write '{}'", html_file.display()))?;
+    }
     if opt.json {
         print!("}}");
     }
     Ok(())
 }
 
-fn get_setting(opt: &Opt) -> Setting {
+fn get_setting(opt: &Opt) -> Result<Setting> {
+    let mut user_programs = vec![];
+    for file in &opt.constraint_files {
+        let program =
+            fs::read_to_string(file).context(format!("unable to read '{}'", file.display()))?;
+        user_programs.push(UserProgram::new(&file.display().to_string(), &program)?);
+    }
+    for (count, constraint) in opt.constraints.iter().enumerate() {
+        user_programs.push(UserProgram::new(
+            &format!("inline constraint {}", count + 1),
+            constraint,
+        )?);
+    }
     let setting = if opt.depmat {
         Setting {
             os: false,
             ep: true,
             fp: true,
             fc: true,
+            user_programs,
         }
     } else {
         Setting {
@@ -355,6 +752,7 @@ fn get_setting(opt: &Opt) -> Setting {
             ep: opt.elempath,
             fp: !opt.fwd_propagation_off,
             fc: !opt.founded_constraints_off,
+            user_programs,
         }
     };
     if opt.json {
@@ -362,5 +760,100 @@ fn get_setting(opt: &Opt) -> Setting {
     } else {
         print!("{setting}")
     }
-    setting
+    Ok(setting)
+}
+
+/// collects the observations and inputs of all profiles for the exported network
+fn network_annotations(
+    profiles: &[profile_parser::Profile],
+    inputs: &FactBase,
+    repairs: &[RepairOp],
+) -> Result<Annotations> {
+    let mut annotations = Annotations {
+        inputs: input_nodes(inputs)?,
+        repairs: repairs.to_vec(),
+        ..Annotations::default()
+    };
+    for profile in profiles {
+        annotations.inputs.extend(profile.inputs.iter().cloned());
+        annotations
+            .observations
+            .extend(profile.observations.iter().cloned());
+    }
+    Ok(annotations)
+}
+
+/// writes the annotated network in the requested formats
+fn export_network(
+    graph: &cif_parser::Graph,
+    annotations: &Annotations,
+    graphml_file: &Option<PathBuf>,
+    cytoscape_file: &Option<PathBuf>,
+) -> Result<()> {
+    if let Some(file) = graphml_file {
+        info!("Writing GraphML ...");
+        fs::write(file, to_graphml(graph, annotations)?)
+            .context(format!("unable to write '{}'", file.display()))?;
+    }
+    if let Some(file) = cytoscape_file {
+        info!("Writing Cytoscape.js JSON ...");
+        fs::write(file, to_cytoscape_json(graph, annotations)?)
+            .context(format!("unable to write '{}'", file.display()))?;
+    }
+    Ok(())
+}
+
+/// Reads the profiles of an observation file, named by the file.
+///
+/// A MIDAS file gives a profile for each treatment, named by the file and the treatment.
+fn read_profiles(observation_file: &Path, threshold: f64) -> Result<Vec<profile_parser::Profile>> {
+    let name = format!("{}", observation_file.display());
+    let f = File::open(observation_file)?;
+    if observation_file.extension().is_some_and(|e| e == "csv") {
+        let (midas, warnings) =
+            midas_parser::read(&f, threshold).context(format!("unable to parse '{name}'"))?;
+        for warning in warnings {
+            warn!("{name}: {warning}");
+        }
+        Ok(midas
+            .treatments()
+            .iter()
+            .enumerate()
+            .map(|(i, treatment)| midas.profile(i, &format!("{name}:{treatment}")))
+            .collect())
+    } else {
+        let profile =
+            profile_parser::read(&f, &name).context(format!("unable to parse '{name}'"))?;
+        Ok(vec![profile])
+    }
+}
+
+/// writes the network in the requested formats
+fn write_network(
+    graph: &cif_parser::Graph,
+    sbml_file: &Option<PathBuf>,
+    sif_file: &Option<PathBuf>,
+) -> Result<()> {
+    if let Some(file) = sbml_file {
+        info!("Writing SBML-qual ...");
+        fs::write(file, to_sbml_qual(graph)?)
+            .context(format!("unable to write '{}'", file.display()))?;
+    }
+    if let Some(file) = sif_file {
+        info!("Writing SIF ...");
+        fs::write(file, to_sif(graph)?).context(format!("unable to write '{}'", file.display()))?;
+    }
+    Ok(())
+}
+
+/// adds a section to the HTML report if one is requested
+fn add_section<T: Serialize + ?Sized>(
+    report: &mut Option<HtmlReport>,
+    title: &str,
+    result: &T,
+) -> Result<()> {
+    match report {
+        Some(report) => report.add(title, result),
+        None => Ok(()),
+    }
 }
@@ -0,0 +1,98 @@
+use crate::boolean::{to_statements, BoolExpr};
+use crate::cif_parser::Graph;
+use anyhow::{bail, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Reads a network of logical rules in BoolNet format.
+///
+/// Returns the graph together with warnings about rules that cannot be represented exactly.
+pub fn read(file: &File) -> Result<(Graph, Vec<String>)> {
+    read_renamed(file, |name| name.to_string())
+}
+
+/// Reads a network of logical rules in BoolNet format and renames its nodes, e.g. to resolve aliases.
+///
+/// Each line contains a target and its rule, separated by a comma, e.g. `pi3k, !ras_gap & grb2_sos | akt`.
+/// The rules may use `&`, `|`, `!` and parentheses, as well as the MaBoSS-style
+/// operators `&&`, `||`, `AND`, `OR` and `NOT`.
+/// Every conjunction of the disjunctive normal form of a rule with more than one literal
+/// becomes an AND node, the signs are taken from the negation of the literals.
+/// Rules that only consist of their target declare an input and add no edge.
+pub fn read_renamed(
+    file: &File,
+    mut rename: impl FnMut(&str) -> String,
+) -> Result<(Graph, Vec<String>)> {
+    let mut warnings = vec![];
+    let mut statements = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (target, rule) = match line.split_once(',') {
+            Some((target, rule)) => (target.trim(), rule.trim()),
+            None => bail!(
+                "line {}: expected a target and a rule, found '{line}'",
+                i + 1
+            ),
+        };
+        // the optional header
+        if target.eq_ignore_ascii_case("targets") && rule.eq_ignore_ascii_case("factors") {
+            continue;
+        }
+        let expression = match bnet::expression(rule) {
+            Ok(expression) => expression,
+            Err(e) => bail!("line {}: invalid rule of {target}: {e}", i + 1),
+        };
+        if expression == BoolExpr::Var(target.to_string()) {
+            continue;
+        }
+        match expression.to_dnf() {
+            Some(dnf) => statements.extend(to_statements(target, &dnf, &mut warnings)),
+            None => {
+                warnings.push(format!(
+                    "rule of {target}: the rule is too large for a normal form, using the signs of its variables"
+                ));
+                let dnf: Vec<_> = expression.literals().into_iter().map(|l| vec![l]).collect();
+                statements.extend(to_statements(target, &dnf, &mut warnings));
+            }
+        }
+    }
+    let statements = statements.into_iter().map(|s| s.renamed(&mut rename));
+    Ok((Graph::from_statements(statements), warnings))
+}
+
+peg::parser! { grammar bnet() for str {
+
+    rule whitespace() = quiet!{[' ' | '\t']*}
+
+    rule ident_char() = ['a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | ':' | '-' | '[' | ']']
+
+    rule ident() -> &'input str
+        = $(ident_char()+)
+
+    pub rule expression() -> BoolExpr
+        = whitespace() e:or() whitespace() { e }
+
+    rule or() -> BoolExpr
+        = l:and() ++ (whitespace() ("||" / "|" / "OR" !ident_char()) whitespace()) {
+            if l.len() == 1 { l[0].clone() } else { BoolExpr::Or(l) }
+        }
+
+    rule and() -> BoolExpr
+        = l:not() ++ (whitespace() ("&&" / "&" / "AND" !ident_char()) whitespace()) {
+            if l.len() == 1 { l[0].clone() } else { BoolExpr::And(l) }
+        }
+
+    rule not() -> BoolExpr
+        = ("!" / "NOT" !ident_char()) whitespace() e:not() { BoolExpr::Not(Box::new(e)) }
+        / atom()
+
+    rule atom() -> BoolExpr
+        = "(" whitespace() e:or() whitespace() ")" { e }
+        / ("true" / "TRUE" / "1") !ident_char() { BoolExpr::Const(true) }
+        / ("false" / "FALSE" / "0") !ident_char() { BoolExpr::Const(false) }
+        / s:ident() { BoolExpr::Var(s.to_string()) }
+}}
@@ -0,0 +1,174 @@
+use crate::cif_parser::{Expression, SNode, Statement};
+use std::collections::BTreeMap;
+
+/// Maximal number of conjunctions of a disjunctive normal form
+pub const MAX_CONJUNCTIONS: usize = 1000;
+
+/// Boolean expression over node names, e.g. the update rule of a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoolExpr {
+    Const(bool),
+    Var(String),
+    Not(Box<BoolExpr>),
+    And(Vec<BoolExpr>),
+    Or(Vec<BoolExpr>),
+}
+
+/// A conjunction of literals, `(node, false)` is a negated node
+pub type Conjunction = Vec<(String, bool)>;
+
+impl BoolExpr {
+    /// Returns the disjunctive normal form of the expression.
+    ///
+    /// Contradictory conjunctions and conjunctions that contain another conjunction
+    /// (including duplicates) are removed.
+    /// Returns `None` if the normal form has more than [`MAX_CONJUNCTIONS`] conjunctions.
+    pub fn to_dnf(&self) -> Option<Vec<Conjunction>> {
+        let mut dnf = self.dnf(true)?;
+        // the order of the literals is kept, it determines the name of the AND node
+        for conjunction in &mut dnf {
+            let mut literals = vec![];
+            for literal in conjunction.drain(..) {
+                if !literals.contains(&literal) {
+                    literals.push(literal);
+                }
+            }
+            *conjunction = literals;
+        }
+        dnf.sort_by_key(|conjunction| conjunction.len());
+        let mut minimal: Vec<Conjunction> = vec![];
+        for conjunction in dnf {
+            if !minimal
+                .iter()
+                .any(|m| m.iter().all(|l| conjunction.contains(l)))
+            {
+                minimal.push(conjunction);
+            }
+        }
+        Some(minimal)
+    }
+
+    /// Returns the variables of the expression with the sign of each occurrence,
+    /// i.e. whether it is negated an even number of times, without duplicates
+    pub fn literals(&self) -> Conjunction {
+        let mut literals = vec![];
+        self.collect_literals(true, &mut literals);
+        literals
+    }
+
+    fn collect_literals(&self, positive: bool, literals: &mut Conjunction) {
+        match self {
+            BoolExpr::Const(_) => {}
+            BoolExpr::Var(name) => {
+                if !literals.contains(&(name.clone(), positive)) {
+                    literals.push((name.clone(), positive));
+                }
+            }
+            BoolExpr::Not(expr) => expr.collect_literals(!positive, literals),
+            BoolExpr::And(exprs) | BoolExpr::Or(exprs) => {
+                for expr in exprs {
+                    expr.collect_literals(positive, literals);
+                }
+            }
+        }
+    }
+
+    /// dnf of the expression (or its negation if `positive` is false)
+    fn dnf(&self, positive: bool) -> Option<Vec<Conjunction>> {
+        match self {
+            BoolExpr::Const(value) => Some(if *value == positive {
+                vec![vec![]]
+            } else {
+                vec![]
+            }),
+            BoolExpr::Var(name) => Some(vec![vec![(name.clone(), positive)]]),
+            BoolExpr::Not(expr) => expr.dnf(!positive),
+            BoolExpr::And(exprs) if positive => product(exprs, positive),
+            BoolExpr::Or(exprs) if !positive => product(exprs, positive),
+            BoolExpr::And(exprs) | BoolExpr::Or(exprs) => {
+                let mut dnf = vec![];
+                for expr in exprs {
+                    dnf.extend(expr.dnf(positive)?);
+                    if dnf.len() > MAX_CONJUNCTIONS {
+                        return None;
+                    }
+                }
+                Some(dnf)
+            }
+        }
+    }
+}
+
+/// dnf of the conjunction of the expressions
+fn product(exprs: &[BoolExpr], positive: bool) -> Option<Vec<Conjunction>> {
+    let mut dnf: Vec<Conjunction> = vec![vec![]];
+    for expr in exprs {
+        let other = expr.dnf(positive)?;
+        let mut combined = vec![];
+        for left in &dnf {
+            for right in &other {
+                let mut conjunction = left.clone();
+                conjunction.extend(right.iter().cloned());
+                // x & !x is never true
+                if !conjunction
+                    .iter()
+                    .any(|(n, s)| conjunction.contains(&(n.clone(), !s)))
+                {
+                    combined.push(conjunction);
+                }
+            }
+        }
+        if combined.len() > MAX_CONJUNCTIONS {
+            return None;
+        }
+        dnf = combined;
+    }
+    Some(dnf)
+}
+
+/// Returns the CIF statements of a rule in disjunctive normal form.
+///
+/// Each conjunction with more than one literal becomes an AND node.
+/// A warning is added for every node that occurs both negated and not negated,
+/// since the influence graph cannot represent such a rule exactly.
+pub fn to_statements(
+    target: &str,
+    dnf: &[Conjunction],
+    warnings: &mut Vec<String>,
+) -> Vec<Statement> {
+    let mut signs: BTreeMap<&str, (bool, bool)> = BTreeMap::new();
+    for (name, positive) in dnf.iter().flatten() {
+        let entry = signs.entry(name).or_default();
+        if *positive {
+            entry.0 = true;
+        } else {
+            entry.1 = true;
+        }
+    }
+    for (name, _) in signs.iter().filter(|(_, (p, n))| *p && *n) {
+        warnings.push(format!(
+            "rule of {target}: {name} occurs both positive and negated, the influence graph contains both signs"
+        ));
+    }
+    dnf.iter()
+        .filter(|conjunction| !conjunction.is_empty())
+        .map(|conjunction| {
+            let mut expressions: Vec<Expression> = conjunction
+                .iter()
+                .map(|(name, positive)| {
+                    if *positive {
+                        Expression::Plain(name.clone())
+                    } else {
+                        Expression::Negated(name.clone())
+                    }
+                })
+                .collect();
+            let start = if expressions.len() == 1 {
+                SNode::Single(expressions.remove(0))
+            } else {
+                SNode::List(expressions)
+            };
+            Statement::new(start, target)
+        })
+        .collect()
+}
@@ -1,30 +1,29 @@
-use crate::{FactBase, NodeId, ObsELabel, ToSymbol};
+use crate::profile_parser::Behavior;
+use crate::{FactBase, NodeId, ObsELabel, Prediction, RepairOp, ToSymbol};
 use anyhow::Result;
 use clingo::*;
 use serde::Serialize;
+use std::fmt;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 
 pub fn read(file: &File) -> Result<Graph> {
+    read_renamed(file, |name| name.to_string())
+}
+
+/// Reads a network in CIF format and renames its nodes, e.g. to resolve aliases
+pub fn read_renamed(file: &File, mut rename: impl FnMut(&str) -> String) -> Result<Graph> {
     let file = BufReader::new(file);
-    let mut graph = Graph::empty();
+    let mut statements = vec![];
     for line in file.lines() {
         let l1 = line?;
         let l = l1.trim();
         if !l.is_empty() {
-            graph.add(cif::statement(l)?);
+            statements.push(cif::statement(l)?.renamed(&mut rename));
         }
     }
-    graph.or_nodes.sort();
-    graph.or_nodes.dedup();
-    graph.and_nodes.sort();
-    graph.and_nodes.dedup();
-    graph.p_edges.sort();
-    graph.p_edges.dedup();
-    graph.n_edges.sort();
-    graph.n_edges.dedup();
-    Ok(graph)
+    Ok(Graph::from_statements(statements))
 }
 
 #[derive(Debug, Clone, ToSymbol)]
@@ -32,11 +31,20 @@ struct Vertex {
     node: NodeId,
 }
 // #[derive(ToSymbol)]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
 pub enum EdgeSign {
     Plus,
     Minus,
 }
+impl EdgeSign {
+    fn product(self, other: EdgeSign) -> EdgeSign {
+        if self == other {
+            EdgeSign::Plus
+        } else {
+            EdgeSign::Minus
+        }
+    }
+}
 impl ToSymbol for EdgeSign {
     fn symbol(&self) -> Result<Symbol, ClingoError> {
         Ok(match self {
@@ -81,6 +89,124 @@ impl NetworkStatistics {
     }
 }
 
+/// Linear chain of nodes that was merged into a single edge
+#[derive(Debug, Clone, Serialize)]
+pub struct Chain {
+    pub start: NodeId,
+    pub target: NodeId,
+    pub sign: EdgeSign,
+    /// the merged nodes with their sign relative to the start node
+    pub nodes: Vec<(NodeId, EdgeSign)>,
+}
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        let mut last = EdgeSign::Plus;
+        for (node, sign) in &self.nodes {
+            write!(f, " {} {node}", arrow(last.product(*sign)))?;
+            last = *sign;
+        }
+        write!(f, " {} {}", arrow(last.product(self.sign)), self.target)
+    }
+}
+fn arrow(sign: EdgeSign) -> &'static str {
+    match sign {
+        EdgeSign::Plus => "->",
+        EdgeSign::Minus => "-|",
+    }
+}
+
+/// Mapping from a compressed graph to the original graph
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Compression {
+    pub chains: Vec<Chain>,
+    /// unobserved dead ends that were removed
+    pub removed_nodes: Vec<NodeId>,
+}
+impl Compression {
+    pub fn print(&self) {
+        println!("\n## Network compression\n");
+        let merged: usize = self.chains.iter().map(|c| c.nodes.len()).sum();
+        println!("- Merged nodes: {merged} (in {} chains)", self.chains.len());
+        println!("- Removed dead ends: {}", self.removed_nodes.len());
+    }
+
+    /// Adds the predictions for the merged nodes,
+    /// they are derived from the prediction of the start node of their chain.
+    ///
+    /// This is only sound with forward propagation (`fp`), without it a merged node may not change
+    /// although its start node does, and the merged nodes are left without prediction.
+    pub fn expand_predictions(&self, predictions: &[Prediction], fp: bool) -> Vec<Prediction> {
+        let mut expanded = predictions.to_vec();
+        if !fp {
+            return expanded;
+        }
+        for chain in &self.chains {
+            let start = match &chain.start {
+                NodeId::Or(name) => name,
+                NodeId::And(_) => continue,
+            };
+            if let Some(prediction) = predictions.iter().find(|p| &p.node == start) {
+                for (node, sign) in &chain.nodes {
+                    expanded.push(Prediction {
+                        node: node.to_string(),
+                        behavior: match sign {
+                            EdgeSign::Plus => prediction.behavior,
+                            EdgeSign::Minus => inverse(prediction.behavior),
+                        },
+                    });
+                }
+            }
+        }
+        expanded
+    }
+
+    /// Maps the removal or sign flip of a merged edge to the first edge of its chain
+    pub fn expand_repair(&self, repair: RepairOp) -> RepairOp {
+        if let RepairOp::RemoveEdge(edge) | RepairOp::FlipEdgeSign(edge) = &repair {
+            if let Some(chain) = self
+                .chains
+                .iter()
+                .find(|c| c.start == edge.start && c.target == edge.target)
+            {
+                let (node, sign) = &chain.nodes[0];
+                let first = ObsELabel {
+                    start: chain.start.clone(),
+                    target: node.clone(),
+                    sign: *sign,
+                };
+                return match repair {
+                    RepairOp::RemoveEdge(_) => RepairOp::RemoveEdge(first),
+                    _ => RepairOp::FlipEdgeSign(first),
+                };
+            }
+        }
+        repair
+    }
+
+    /// Reports a labeling and its repairs on the original graph
+    pub fn expand_labeling(
+        &self,
+        (labels, repairs): (Vec<Prediction>, Vec<RepairOp>),
+    ) -> (Vec<Prediction>, Vec<RepairOp>) {
+        // the labels of the start node are one valid completion of the labeling
+        let labels = self.expand_predictions(&labels, true);
+        let repairs = repairs.into_iter().map(|r| self.expand_repair(r)).collect();
+        (labels, repairs)
+    }
+}
+
+fn inverse(behavior: Behavior) -> Behavior {
+    match behavior {
+        Behavior::Plus => Behavior::Minus,
+        Behavior::Minus => Behavior::Plus,
+        Behavior::Zero => Behavior::Zero,
+        Behavior::NotPlus => Behavior::NotMinus,
+        Behavior::NotMinus => Behavior::NotPlus,
+        Behavior::Change => Behavior::Change,
+    }
+}
+
 impl Graph {
     pub fn empty() -> Graph {
         Graph {
@@ -91,6 +217,23 @@ impl Graph {
             u_edges: vec![],
         }
     }
+
+    /// Builds a graph from CIF statements, e.g. converted from other formats
+    pub fn from_statements(statements: impl IntoIterator<Item = Statement>) -> Graph {
+        let mut graph = Graph::empty();
+        for statement in statements {
+            graph.add(statement);
+        }
+        graph.or_nodes.sort();
+        graph.or_nodes.dedup();
+        graph.and_nodes.sort();
+        graph.and_nodes.dedup();
+        graph.p_edges.sort();
+        graph.p_edges.dedup();
+        graph.n_edges.sort();
+        graph.n_edges.dedup();
+        graph
+    }
     pub fn or_nodes(&self) -> &[NodeId] {
         &self.or_nodes
     }
@@ -107,6 +250,101 @@ impl Graph {
         &self.u_edges
     }
 
+    /// Removes a node and all its edges.
+    /// AND nodes that the node takes part in are removed as well.
+    /// Returns false if the node is not in the graph.
+    pub fn remove_node(&mut self, node: &NodeId) -> bool {
+        if !self.or_nodes.contains(node) && !self.and_nodes.contains(node) {
+            return false;
+        }
+        let mut removed = vec![node.clone()];
+        for (s, t) in self
+            .p_edges
+            .iter()
+            .chain(&self.n_edges)
+            .chain(&self.u_edges)
+        {
+            if s == node && matches!(t, NodeId::And(_)) {
+                removed.push(t.clone());
+            }
+        }
+        self.or_nodes.retain(|n| !removed.contains(n));
+        self.and_nodes.retain(|n| !removed.contains(n));
+        for edges in [&mut self.p_edges, &mut self.n_edges, &mut self.u_edges] {
+            edges.retain(|(s, t)| !removed.contains(s) && !removed.contains(t));
+        }
+        true
+    }
+
+    /// Removes the edges from start to target.
+    /// Returns false if there is no such edge.
+    pub fn remove_edge(&mut self, start: &NodeId, target: &NodeId) -> bool {
+        let mut found = false;
+        for edges in [&mut self.p_edges, &mut self.n_edges, &mut self.u_edges] {
+            let len = edges.len();
+            edges.retain(|(s, t)| s != start || t != target);
+            found |= edges.len() != len;
+        }
+        found
+    }
+
+    /// Adds an OR node without edges, if it is not in the graph
+    pub fn add_node(&mut self, node: &NodeId) {
+        if !self.or_nodes.contains(node) {
+            self.or_nodes.push(node.clone());
+            self.or_nodes.sort();
+        }
+    }
+
+    /// Adds an edge between two OR nodes, the nodes are added if necessary
+    pub fn add_edge(&mut self, start: &NodeId, target: &NodeId, sign: EdgeSign) {
+        for node in [start, target] {
+            if !self.or_nodes.contains(node) && !self.and_nodes.contains(node) {
+                self.or_nodes.push(node.clone());
+            }
+        }
+        let edges = match sign {
+            EdgeSign::Plus => &mut self.p_edges,
+            EdgeSign::Minus => &mut self.n_edges,
+        };
+        let edge = (start.clone(), target.clone());
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
+    }
+
+    /// Returns the graph after applying the repairs of the edges,
+    /// repairs of the observations are ignored
+    pub fn repaired(&self, repairs: &[RepairOp]) -> Graph {
+        let mut graph = self.clone();
+        for repair in repairs {
+            match repair {
+                RepairOp::AddEdge(e) => graph.add_edge(&e.start, &e.target, e.sign),
+                RepairOp::RemoveEdge(e) => {
+                    graph.remove_edge(&e.start, &e.target);
+                }
+                RepairOp::FlipEdgeDirection(e) => {
+                    graph.remove_edge(&e.start, &e.target);
+                    graph.add_edge(&e.target, &e.start, e.sign);
+                }
+                RepairOp::FlipEdgeSign(e) => {
+                    graph.remove_edge(&e.start, &e.target);
+                    graph.add_edge(&e.start, &e.target, e.sign.product(EdgeSign::Minus));
+                }
+                RepairOp::FlipNodeSign { .. } | RepairOp::NewInfluence { .. } => {}
+            }
+        }
+        graph.or_nodes.sort();
+        graph
+    }
+
+    /// Removes all edges that target the node
+    pub fn remove_incoming_edges(&mut self, node: &NodeId) {
+        for edges in [&mut self.p_edges, &mut self.n_edges, &mut self.u_edges] {
+            edges.retain(|(_, t)| t != node);
+        }
+    }
+
     fn add(&mut self, stm: Statement) {
         let targetnode = NodeId::Or(stm.target);
         self.or_nodes.push(targetnode.clone());
@@ -172,6 +410,150 @@ impl Graph {
         }
     }
 
+    /// Compresses the graph for faster solving.
+    ///
+    /// Linear chains of nodes that are not in `keep` (e.g. observed nodes and inputs)
+    /// are merged into single signed edges and dead ends that are not in `keep` are removed.
+    /// Returns the compressed graph and the mapping to the original graph.
+    pub fn compress(&self, keep: &[NodeId]) -> (Graph, Compression) {
+        let mut graph = self.clone();
+        let mut compression = Compression::default();
+        loop {
+            let mut changed = false;
+
+            // remove dead ends
+            let sinks: Vec<NodeId> = graph
+                .or_nodes
+                .iter()
+                .chain(&graph.and_nodes)
+                .filter(|n| !keep.contains(n) && !graph.has_outgoing_edges(n))
+                .cloned()
+                .collect();
+            for node in sinks {
+                graph.or_nodes.retain(|n| n != &node);
+                graph.and_nodes.retain(|n| n != &node);
+                graph.remove_incoming_edges(&node);
+                // chains that end in a removed node are removed with it
+                let (removed, chains): (Vec<Chain>, Vec<Chain>) =
+                    compression.chains.drain(..).partition(|c| c.target == node);
+                compression.chains = chains;
+                for chain in removed {
+                    for (n, _) in chain.nodes {
+                        compression.removed_nodes.push(n);
+                    }
+                }
+                compression.removed_nodes.push(node);
+                changed = true;
+            }
+
+            // merge linear chains
+            let candidates: Vec<NodeId> = graph
+                .or_nodes
+                .iter()
+                .filter(|n| !keep.contains(n))
+                .cloned()
+                .collect();
+            for node in candidates {
+                let (start, first, target, second) = match graph.linear(&node) {
+                    Some(linear) => linear,
+                    None => continue,
+                };
+                if start == node
+                    || target == node
+                    || start == target
+                    || matches!(start, NodeId::And(_))
+                    || graph.has_edge(&start, &target)
+                {
+                    continue;
+                }
+                graph.or_nodes.retain(|n| n != &node);
+                for edges in [&mut graph.p_edges, &mut graph.n_edges] {
+                    edges.retain(|(s, t)| s != &node && t != &node);
+                }
+                let sign = first.product(second);
+                match sign {
+                    EdgeSign::Plus => graph.p_edges.push((start.clone(), target.clone())),
+                    EdgeSign::Minus => graph.n_edges.push((start.clone(), target.clone())),
+                }
+
+                let mut nodes = vec![];
+                if let Some(i) = compression
+                    .chains
+                    .iter()
+                    .position(|c| c.start == start && c.target == node)
+                {
+                    nodes.extend(compression.chains.remove(i).nodes);
+                }
+                nodes.push((node.clone(), first));
+                if let Some(i) = compression
+                    .chains
+                    .iter()
+                    .position(|c| c.start == node && c.target == target)
+                {
+                    let chain = compression.chains.remove(i);
+                    nodes.extend(chain.nodes.into_iter().map(|(n, s)| (n, first.product(s))));
+                }
+                compression.chains.push(Chain {
+                    start,
+                    target,
+                    sign,
+                    nodes,
+                });
+                changed = true;
+            }
+            if !changed {
+                break;
+            }
+        }
+        graph.p_edges.sort();
+        graph.n_edges.sort();
+        compression.removed_nodes.sort();
+        (graph, compression)
+    }
+
+    fn has_outgoing_edges(&self, node: &NodeId) -> bool {
+        self.p_edges
+            .iter()
+            .chain(&self.n_edges)
+            .chain(&self.u_edges)
+            .any(|(s, _)| s == node)
+    }
+
+    fn has_edge(&self, start: &NodeId, target: &NodeId) -> bool {
+        self.p_edges
+            .iter()
+            .chain(&self.n_edges)
+            .chain(&self.u_edges)
+            .any(|(s, t)| s == start && t == target)
+    }
+
+    /// returns the regulator and the target of a node with exactly one signed incoming
+    /// and one signed outgoing edge
+    fn linear(&self, node: &NodeId) -> Option<(NodeId, EdgeSign, NodeId, EdgeSign)> {
+        let mut incoming = vec![];
+        let mut outgoing = vec![];
+        for (edges, sign) in [
+            (&self.p_edges, Some(EdgeSign::Plus)),
+            (&self.n_edges, Some(EdgeSign::Minus)),
+            (&self.u_edges, None),
+        ] {
+            for (s, t) in edges {
+                if t == node {
+                    incoming.push((s.clone(), sign));
+                }
+                if s == node {
+                    outgoing.push((t.clone(), sign));
+                }
+            }
+        }
+        match (&incoming[..], &outgoing[..]) {
+            ([(start, Some(first))], [(target, Some(second))]) => {
+                Some((start.clone(), *first, target.clone(), *second))
+            }
+            _ => None,
+        }
+    }
+
     pub fn to_facts(&self) -> FactBase {
         let mut facts = FactBase::new();
         for node in &self.or_nodes {
@@ -218,6 +600,40 @@ pub struct Statement {
     start: SNode,
     target: String,
 }
+impl Statement {
+    pub fn new(start: SNode, target: &str) -> Statement {
+        Statement {
+            start,
+            target: target.to_string(),
+        }
+    }
+    pub fn start(&self) -> &SNode {
+        &self.start
+    }
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+    pub(crate) fn renamed(self, rename: &mut impl FnMut(&str) -> String) -> Statement {
+        let mut rename_expr = |expr: Expression| match expr {
+            Expression::Plain(s) => Expression::Plain(rename(&s)),
+            Expression::Negated(s) => Expression::Negated(rename(&s)),
+            Expression::Unknown(s) => Expression::Unknown(rename(&s)),
+        };
+        let start = match self.start {
+            SNode::Single(expr) => SNode::Single(rename_expr(expr)),
+            SNode::List(list) => SNode::List(list.into_iter().map(rename_expr).collect()),
+        };
+        Statement {
+            start,
+            target: rename(&self.target),
+        }
+    }
+}
+
+/// Parses a single line of a CIF file
+pub fn parse_statement(line: &str) -> Result<Statement> {
+    Ok(cif::statement(line)?)
+}
 
 #[derive(Debug, Clone)]
 pub enum SNode {
@@ -0,0 +1,288 @@
+use crate::{
+    get_mcos, get_mcos_labelings, get_predictions_under_mcos, get_predictions_under_scenfit,
+    get_scenfit, get_scenfit_labelings, Prediction, RepairOp, Setting,
+};
+use anyhow::{anyhow, Result};
+use clingo::{FactBase, Symbol};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Graph, profile and input facts of an independent part of the problem
+#[derive(Debug, Clone)]
+pub struct SubProblem {
+    pub graph: FactBase,
+    pub profile: FactBase,
+    pub inputs: FactBase,
+}
+
+/// Splits the problem into the weakly connected components of the network.
+///
+/// Observations and inputs are assigned to the component of their node,
+/// every component keeps all experiments so that unobserved components are still labeled.
+/// Facts of other predicates, like the steps of a time series, are copied to every component.
+pub fn split(graph: &FactBase, profile: &FactBase, inputs: &FactBase) -> Result<Vec<SubProblem>> {
+    let mut components = UnionFind::default();
+    for fact in graph.iter() {
+        match (fact.name()?, fact.arguments()?.as_slice()) {
+            ("vertex", [node]) => {
+                components.find(*node);
+            }
+            ("obs_e_label", [start, target, _]) | ("edge", [start, target]) => {
+                components.union(*start, *target)
+            }
+            _ => {}
+        }
+    }
+    let mut experiments = HashSet::new();
+    for fact in profile.iter() {
+        match (fact.name()?, fact.arguments()?.as_slice()) {
+            ("step", [previous, step, next]) => {
+                experiments.extend([*previous, *step, *next]);
+            }
+            (_, [experiment, node, ..]) => {
+                experiments.insert(*experiment);
+                components.find(*node);
+            }
+            (_, [experiment]) => {
+                experiments.insert(*experiment);
+            }
+            _ => {}
+        }
+    }
+    for fact in inputs.iter() {
+        if let [node] = fact.arguments()?.as_slice() {
+            components.find(*node);
+        }
+    }
+
+    let mut index = HashMap::new();
+    let mut problems: Vec<SubProblem> = vec![];
+    let mut shared = vec![];
+    let mut add = |fact: &Symbol,
+                   node: Option<Symbol>,
+                   part: fn(&mut SubProblem) -> &mut FactBase| {
+        match node {
+            Some(node) => {
+                let root = components.find(node);
+                let i = *index.entry(root).or_insert_with(|| {
+                    problems.push(SubProblem {
+                        graph: FactBase::new(),
+                        profile: FactBase::new(),
+                        inputs: FactBase::new(),
+                    });
+                    problems.len() - 1
+                });
+                part(&mut problems[i]).insert(fact);
+            }
+            None => shared.push((*fact, part)),
+        }
+    };
+    for fact in graph.iter() {
+        let node = match (fact.name()?, fact.arguments()?.as_slice()) {
+            ("vertex", [node]) | ("obs_e_label", [node, _, _]) | ("edge", [node, _]) => Some(*node),
+            _ => None,
+        };
+        add(fact, node, |p| &mut p.graph);
+    }
+    for fact in profile.iter() {
+        let node = match fact.name()? {
+            "step" => None,
+            _ => fact.arguments()?.get(1).copied(),
+        };
+        add(fact, node, |p| &mut p.profile);
+    }
+    for fact in inputs.iter() {
+        let node = fact.arguments()?.first().copied();
+        add(fact, node, |p| &mut p.inputs);
+    }
+
+    for problem in &mut problems {
+        for (fact, part) in &shared {
+            part(problem).insert(fact);
+        }
+        for experiment in &experiments {
+            problem
+                .profile
+                .insert(&Symbol::create_function("exp", &[*experiment], true)?);
+        }
+    }
+    Ok(problems)
+}
+
+#[derive(Default)]
+struct UnionFind {
+    parent: HashMap<Symbol, Symbol>,
+}
+impl UnionFind {
+    fn find(&mut self, node: Symbol) -> Symbol {
+        let parent = *self.parent.entry(node).or_insert(node);
+        if parent == node {
+            return node;
+        }
+        let root = self.find(parent);
+        self.parent.insert(node, root);
+        root
+    }
+    fn union(&mut self, a: Symbol, b: Symbol) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a != b {
+            self.parent.insert(a, b);
+        }
+    }
+}
+
+/// Runs the query on all sub-problems using all available cores.
+/// The results are returned in the order of the sub-problems.
+pub fn solve_parallel<P, T, F>(problems: &[P], query: F) -> Result<Vec<T>>
+where
+    P: Sync,
+    T: Send,
+    F: Fn(&P) -> Result<T> + Sync,
+{
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(problems.len());
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<T>>>> =
+        Mutex::new(problems.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= problems.len() {
+                    break;
+                }
+                let result = query(&problems[i]);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .map_err(|_| anyhow!("a worker thread panicked"))?
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err(anyhow!("a sub-problem was not solved"))))
+        .collect()
+}
+
+/// labels of the nodes together with the repairs
+pub type Labeling = (Vec<Prediction>, Vec<RepairOp>);
+
+/// Combines the labelings of the sub-problems into labelings of the whole problem.
+///
+/// Every combination of the labelings of the components is a labeling of the whole problem,
+/// at most `number` combinations are returned, 0=all.
+/// The combinations are built one at a time, their number is the product of the numbers of
+/// labelings of the components.
+pub fn combine_labelings(components: Vec<Vec<Labeling>>, number: u32) -> Combinations {
+    let done = components.iter().any(|labelings| labelings.is_empty());
+    Combinations {
+        indices: vec![0; components.len()],
+        components,
+        remaining: match number {
+            0 => None,
+            n => Some(n as usize),
+        },
+        done,
+    }
+}
+
+/// Iterator over the combinations of the labelings of the sub-problems
+pub struct Combinations {
+    components: Vec<Vec<Labeling>>,
+    /// the current labeling of every component
+    indices: Vec<usize>,
+    remaining: Option<usize>,
+    done: bool,
+}
+impl Iterator for Combinations {
+    type Item = Labeling;
+    fn next(&mut self) -> Option<Labeling> {
+        if self.done || self.remaining == Some(0) {
+            return None;
+        }
+        let mut labels = vec![];
+        let mut repairs = vec![];
+        for (labelings, &i) in self.components.iter().zip(&self.indices) {
+            let (l, r) = &labelings[i];
+            labels.extend(l.iter().cloned());
+            repairs.extend(r.iter().cloned());
+        }
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        // advance to the next combination, the last component changes fastest
+        self.done = true;
+        for (labelings, i) in self.components.iter().zip(&mut self.indices).rev() {
+            *i += 1;
+            if *i < labelings.len() {
+                self.done = false;
+                break;
+            }
+            *i = 0;
+        }
+        Some((labels, repairs))
+    }
+}
+
+/// returns the scenfit of data and model as sum over all sub-problems
+pub fn get_scenfit_split(problems: &[SubProblem], setting: &Setting) -> Result<i64> {
+    let scenfits = solve_parallel(problems, |p| {
+        get_scenfit(&p.graph, &p.profile, &p.inputs, setting)
+    })?;
+    Ok(scenfits.into_iter().sum())
+}
+
+/// returns the mcos of data and model as sum over all sub-problems
+pub fn get_mcos_split(problems: &[SubProblem], setting: &Setting) -> Result<i64> {
+    let mcos = solve_parallel(problems, |p| {
+        get_mcos(&p.graph, &p.profile, &p.inputs, setting)
+    })?;
+    Ok(mcos.into_iter().sum())
+}
+
+pub fn get_predictions_under_scenfit_split(
+    problems: &[SubProblem],
+    setting: &Setting,
+) -> Result<Vec<Prediction>> {
+    let predictions = solve_parallel(problems, |p| {
+        get_predictions_under_scenfit(&p.graph, &p.profile, &p.inputs, setting)
+    })?;
+    Ok(predictions.into_iter().flatten().collect())
+}
+
+pub fn get_predictions_under_mcos_split(
+    problems: &[SubProblem],
+    setting: &Setting,
+) -> Result<Vec<Prediction>> {
+    let predictions = solve_parallel(problems, |p| {
+        get_predictions_under_mcos(&p.graph, &p.profile, &p.inputs, setting)
+    })?;
+    Ok(predictions.into_iter().flatten().collect())
+}
+
+pub fn get_scenfit_labelings_split(
+    problems: &[SubProblem],
+    number: u32,
+    setting: &Setting,
+) -> Result<Combinations> {
+    let labelings = solve_parallel(problems, |p| {
+        Ok(get_scenfit_labelings(&p.graph, &p.profile, &p.inputs, number, setting)?.collect())
+    })?;
+    Ok(combine_labelings(labelings, number))
+}
+
+pub fn get_mcos_labelings_split(
+    problems: &[SubProblem],
+    number: u32,
+    setting: &Setting,
+) -> Result<Combinations> {
+    let labelings = solve_parallel(problems, |p| {
+        Ok(get_mcos_labelings(&p.graph, &p.profile, &p.inputs, number, setting)?.collect())
+    })?;
+    Ok(combine_labelings(labelings, number))
+}
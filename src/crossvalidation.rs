@@ -0,0 +1,129 @@
+use crate::components::solve_parallel;
+use crate::evaluation::match_score;
+use crate::profile_parser::{Behavior, Observation, Profile};
+use crate::{get_predictions_under_mcos, get_predictions_under_scenfit, NodeId, Setting};
+use anyhow::Result;
+use clingo::FactBase;
+use serde::Serialize;
+
+/// Prediction of a hidden observation
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeRecovery {
+    pub node: NodeId,
+    pub fold: usize,
+    pub observed: Behavior,
+    pub predicted: Option<Behavior>,
+    /// match score of prediction and observation, 0 if the node was not predicted
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossValidation {
+    pub folds: usize,
+    pub nodes: Vec<NodeRecovery>,
+    /// average match score over all hidden observations
+    pub accuracy: f64,
+    /// fraction of hidden observations that were recovered exactly
+    pub recovered: f64,
+    /// nodes without prediction when their observation is hidden
+    pub unpredictable: Vec<NodeId>,
+}
+impl CrossValidation {
+    pub fn print(&self) {
+        println!("\n## Cross-validation\n");
+        println!("- Folds: {}", self.folds);
+        println!("- Hidden observations: {}", self.nodes.len());
+        println!("- Recovered observations: {:.3}", self.recovered);
+        println!("- Predictive accuracy: {:.3}", self.accuracy);
+        println!("\n### Recovery per node\n");
+        for n in &self.nodes {
+            match n.predicted {
+                Some(predicted) => println!(
+                    "- {}: observed {}, predicted {} (score {:.2})",
+                    n.node, n.observed, predicted, n.score
+                ),
+                None => println!("- {}: observed {}, not predicted", n.node, n.observed),
+            }
+        }
+        println!("\n### Unpredictable nodes: {}\n", self.unpredictable.len());
+        for node in &self.unpredictable {
+            println!("- {node}");
+        }
+    }
+}
+
+/// Cross-validates the network against the observations of the profile.
+///
+/// The observations are split into `folds` parts, 0 means leave-one-out.
+/// The observations of each part are hidden in turn and compared to the predictions
+/// under mcos (or scenfit) for the remaining observations, see [`match_score`].
+/// The folds are solved in parallel.
+pub fn cross_validate(
+    graph: &FactBase,
+    profile: &Profile,
+    inputs: &FactBase,
+    folds: usize,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<CrossValidation> {
+    let observations = &profile.observations;
+    let folds = if folds == 0 || folds > observations.len() {
+        observations.len()
+    } else {
+        folds
+    };
+    let parts: Vec<usize> = (0..folds).collect();
+    let results = solve_parallel(&parts, |fold| {
+        let mut hidden: Vec<&Observation> = vec![];
+        let mut training = profile.clone();
+        training.observations.clear();
+        for (i, observation) in observations.iter().enumerate() {
+            if i % folds == *fold {
+                hidden.push(observation);
+            } else {
+                training.observations.push(observation.clone());
+            }
+        }
+        let facts = training.to_facts();
+        let predictions = if scenfit {
+            get_predictions_under_scenfit(graph, &facts, inputs, setting)?
+        } else {
+            get_predictions_under_mcos(graph, &facts, inputs, setting)?
+        };
+        Ok(hidden
+            .into_iter()
+            .map(|o| {
+                let name = o.node.to_string();
+                let predicted = predictions
+                    .iter()
+                    .find(|p| p.node == name)
+                    .map(|p| p.behavior);
+                NodeRecovery {
+                    node: o.node.clone(),
+                    fold: *fold,
+                    observed: o.behavior,
+                    predicted,
+                    score: predicted.map_or(0.0, |p| match_score(p, o.behavior)),
+                }
+            })
+            .collect::<Vec<_>>())
+    })?;
+    let mut nodes: Vec<NodeRecovery> = results.into_iter().flatten().collect();
+    nodes.sort_by(|a, b| a.node.cmp(&b.node));
+
+    let total = nodes.len().max(1) as f64;
+    let accuracy = nodes.iter().map(|n| n.score).sum::<f64>() / total;
+    let recovered = nodes.iter().filter(|n| n.score == 1.0).count() as f64 / total;
+    let unpredictable = nodes
+        .iter()
+        .filter(|n| n.predicted.is_none())
+        .map(|n| n.node.clone())
+        .collect();
+    Ok(CrossValidation {
+        folds,
+        nodes,
+        accuracy,
+        recovered,
+        unpredictable,
+    })
+}
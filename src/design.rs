@@ -0,0 +1,228 @@
+use crate::components::solve_parallel;
+use crate::profile_parser::{Behavior, Observation, Profile};
+use crate::{
+    get_mcos, get_predictions_under_mcos, get_predictions_under_scenfit, get_scenfit, NodeId,
+    Prediction, Setting,
+};
+use anyhow::Result;
+use clingo::FactBase;
+use log::info;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Score of measuring a set of nodes in the next experiment
+#[derive(Debug, Clone, Serialize)]
+pub struct MeasurementScore {
+    /// nodes to measure
+    pub nodes: Vec<NodeId>,
+    /// number of outcomes that are consistent with the current model and data
+    pub outcomes: usize,
+    /// additional predictions in the worst case outcome
+    pub min_gain: usize,
+    /// additional predictions in the best case outcome
+    pub max_gain: usize,
+    /// average number of additional predictions over all possible outcomes
+    pub mean_gain: f64,
+}
+impl MeasurementScore {
+    pub fn print(&self) {
+        let nodes: Vec<String> = self.nodes.iter().map(|n| n.to_string()).collect();
+        println!(
+            "- {}: min {}, mean {:.2}, max {} additional predictions ({} possible outcomes)",
+            nodes.join(", "),
+            self.min_gain,
+            self.mean_gain,
+            self.max_gain,
+            self.outcomes
+        );
+    }
+}
+
+const OUTCOMES: [Behavior; 3] = [Behavior::Plus, Behavior::Minus, Behavior::Zero];
+
+/// Ranks the unobserved nodes by the number of additional predictions measuring them would give.
+///
+/// Every outcome of a measurement is checked against the current model and data,
+/// only outcomes that do not increase the mcos (or scenfit) are considered.
+/// For a budget of `k > 1` nodes the sets are extended greedily,
+/// the result contains the best `k - 1` nodes together with every remaining candidate.
+/// The candidates are evaluated in parallel.
+///
+/// # Arguments:
+///
+/// + budget - number of nodes that can be measured
+/// + scenfit - use scenfit instead of mcos
+pub fn rank_measurements(
+    graph: &FactBase,
+    profile: &Profile,
+    inputs: &FactBase,
+    budget: usize,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<Vec<MeasurementScore>> {
+    let evaluate = Evaluate {
+        graph,
+        inputs,
+        scenfit,
+        setting,
+    };
+    let baseline = evaluate.consistency(profile)?;
+    let predictions = evaluate.predictions(profile)?;
+    let observed: HashSet<String> = profile
+        .observations
+        .iter()
+        .map(|o| o.node.to_string())
+        .collect();
+    let predicted = count_strong_predictions(&predictions, &observed);
+
+    // nodes with a strong prediction cannot give additional information
+    let strong: HashSet<&str> = predictions
+        .iter()
+        .filter(|p| is_strong(p.behavior))
+        .map(|p| &p.node[..])
+        .collect();
+    let candidates: Vec<NodeId> = graph_nodes(graph)?
+        .into_iter()
+        .filter(|n| !observed.contains(&n.to_string()) && !strong.contains(&n.to_string()[..]))
+        .collect();
+    info!("Candidate nodes for measurement: {}", candidates.len());
+
+    let mut chosen: Vec<NodeId> = vec![];
+    let mut ranking = vec![];
+    for _ in 0..budget.max(1) {
+        let sets: Vec<Vec<NodeId>> = candidates
+            .iter()
+            .filter(|n| !chosen.contains(n))
+            .map(|node| {
+                let mut nodes = chosen.clone();
+                nodes.push(node.clone());
+                nodes
+            })
+            .collect();
+        let scores = solve_parallel(&sets, |nodes| {
+            evaluate.score(profile, nodes, baseline, predicted)
+        })?;
+        ranking = scores.into_iter().flatten().collect();
+        ranking.sort_by(|a, b| {
+            b.min_gain
+                .cmp(&a.min_gain)
+                .then(b.mean_gain.total_cmp(&a.mean_gain))
+                .then(a.outcomes.cmp(&b.outcomes))
+        });
+        match ranking.first() {
+            Some(best) => chosen = best.nodes.clone(),
+            None => break,
+        }
+    }
+    Ok(ranking)
+}
+
+struct Evaluate<'a> {
+    graph: &'a FactBase,
+    inputs: &'a FactBase,
+    scenfit: bool,
+    setting: &'a Setting,
+}
+impl Evaluate<'_> {
+    fn consistency(&self, profile: &Profile) -> Result<i64> {
+        let facts = profile.to_facts();
+        if self.scenfit {
+            get_scenfit(self.graph, &facts, self.inputs, self.setting)
+        } else {
+            get_mcos(self.graph, &facts, self.inputs, self.setting)
+        }
+    }
+    fn predictions(&self, profile: &Profile) -> Result<Vec<Prediction>> {
+        let facts = profile.to_facts();
+        if self.scenfit {
+            get_predictions_under_scenfit(self.graph, &facts, self.inputs, self.setting)
+        } else {
+            get_predictions_under_mcos(self.graph, &facts, self.inputs, self.setting)
+        }
+    }
+    /// returns the score of measuring the nodes or None if no outcome is possible
+    fn score(
+        &self,
+        profile: &Profile,
+        nodes: &[NodeId],
+        baseline: i64,
+        predicted: usize,
+    ) -> Result<Option<MeasurementScore>> {
+        let mut gains = vec![];
+        for outcome in outcomes(nodes.len()) {
+            let mut extended = profile.clone();
+            for (node, behavior) in nodes.iter().zip(outcome) {
+                extended.observations.push(Observation {
+                    node: node.clone(),
+                    behavior,
+                });
+            }
+            if self.consistency(&extended)? != baseline {
+                continue;
+            }
+            let observed: HashSet<String> = extended
+                .observations
+                .iter()
+                .map(|o| o.node.to_string())
+                .collect();
+            let predictions = self.predictions(&extended)?;
+            let new = count_strong_predictions(&predictions, &observed);
+            // the measured nodes are no longer counted as predictions
+            gains.push(new.saturating_sub(predicted));
+        }
+        if gains.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(MeasurementScore {
+            nodes: nodes.to_vec(),
+            outcomes: gains.len(),
+            min_gain: *gains.iter().min().unwrap(),
+            max_gain: *gains.iter().max().unwrap(),
+            mean_gain: gains.iter().sum::<usize>() as f64 / gains.len() as f64,
+        }))
+    }
+}
+
+/// all combinations of outcomes for n measured nodes
+fn outcomes(n: usize) -> Vec<Vec<Behavior>> {
+    let mut combinations = vec![vec![]];
+    for _ in 0..n {
+        let mut next = vec![];
+        for combination in combinations {
+            for behavior in OUTCOMES {
+                let mut c: Vec<Behavior> = combination.clone();
+                c.push(behavior);
+                next.push(c);
+            }
+        }
+        combinations = next;
+    }
+    combinations
+}
+
+fn is_strong(behavior: Behavior) -> bool {
+    matches!(behavior, Behavior::Plus | Behavior::Minus | Behavior::Zero)
+}
+
+fn count_strong_predictions(predictions: &[Prediction], observed: &HashSet<String>) -> usize {
+    predictions
+        .iter()
+        .filter(|p| is_strong(p.behavior) && !observed.contains(&p.node))
+        .count()
+}
+
+/// returns the species nodes of the graph facts
+fn graph_nodes(graph: &FactBase) -> Result<Vec<NodeId>> {
+    let mut nodes = vec![];
+    for symbol in graph.iter() {
+        if symbol.name()? == "vertex" {
+            let node = crate::into_node_id(symbol.arguments()?[0])?;
+            if let NodeId::Or(_) = node {
+                nodes.push(node);
+            }
+        }
+    }
+    nodes.sort();
+    nodes.dedup();
+    Ok(nodes)
+}
@@ -0,0 +1,293 @@
+use crate::cif_parser::Graph;
+use crate::profile_parser::Behavior;
+use crate::{consistency_and_predictions, NodeId, Prediction, Setting};
+use anyhow::Result;
+use clingo::FactBase;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Sign {
+    #[serde(rename = "+")]
+    Plus,
+    #[serde(rename = "-")]
+    Minus,
+    #[serde(rename = "?")]
+    Unknown,
+}
+impl fmt::Display for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sign::Plus => write!(f, "+"),
+            Sign::Minus => write!(f, "-"),
+            Sign::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedEdge {
+    pub start: NodeId,
+    pub target: NodeId,
+    pub signs: Vec<Sign>,
+}
+impl fmt::Display for SignedEdge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} ({})",
+            self.start,
+            self.target,
+            show_signs(&self.signs)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignChange {
+    pub start: NodeId,
+    pub target: NodeId,
+    pub before: Vec<Sign>,
+    pub after: Vec<Sign>,
+}
+impl fmt::Display for SignChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {}: {} becomes {}",
+            self.start,
+            self.target,
+            show_signs(&self.before),
+            show_signs(&self.after)
+        )
+    }
+}
+
+fn show_signs(signs: &[Sign]) -> String {
+    let signs: Vec<String> = signs.iter().map(|s| s.to_string()).collect();
+    signs.join("/")
+}
+
+/// Structural differences between two versions of a network
+#[derive(Debug, Clone, Serialize)]
+pub struct StructuralDiff {
+    pub added_nodes: Vec<NodeId>,
+    pub removed_nodes: Vec<NodeId>,
+    pub added_edges: Vec<SignedEdge>,
+    pub removed_edges: Vec<SignedEdge>,
+    pub sign_changes: Vec<SignChange>,
+}
+impl StructuralDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+            && self.sign_changes.is_empty()
+    }
+    pub fn print(&self) {
+        println!("\n### Structural differences\n");
+        if self.is_empty() {
+            println!("The networks have the same structure.");
+            return;
+        }
+        print_list("Added nodes", &self.added_nodes);
+        print_list("Removed nodes", &self.removed_nodes);
+        print_list("Added edges", &self.added_edges);
+        print_list("Removed edges", &self.removed_edges);
+        print_list("Sign changes", &self.sign_changes);
+    }
+}
+
+/// Prediction of a node in both networks
+#[derive(Debug, Clone, Serialize)]
+pub struct PredictionPair {
+    pub node: String,
+    pub first: Behavior,
+    pub second: Behavior,
+}
+impl fmt::Display for PredictionPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.node, self.first, self.second)
+    }
+}
+
+/// Comparison of two versions of a network with the same observations
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkComparison {
+    pub structure: StructuralDiff,
+    /// mcos (or scenfit) of the first network
+    pub first_consistency: i64,
+    /// mcos (or scenfit) of the second network
+    pub second_consistency: i64,
+    /// predictions only made with the second network
+    pub gained: Vec<Prediction>,
+    /// predictions only made with the first network
+    pub lost: Vec<Prediction>,
+    /// predictions that differ but are compatible, e.g. notPlus and 0
+    pub changed: Vec<PredictionPair>,
+    /// predictions that are incompatible, e.g. + and notPlus
+    pub contradicted: Vec<PredictionPair>,
+}
+impl NetworkComparison {
+    pub fn print(&self) {
+        self.structure.print();
+        println!("\n### Consistency\n");
+        println!("- first network: {}", self.first_consistency);
+        println!("- second network: {}", self.second_consistency);
+        println!("\n### Prediction differences\n");
+        if self.gained.is_empty()
+            && self.lost.is_empty()
+            && self.changed.is_empty()
+            && self.contradicted.is_empty()
+        {
+            println!("The networks give the same predictions.");
+            return;
+        }
+        print_list("Gained predictions", &self.gained);
+        print_list("Lost predictions", &self.lost);
+        print_list("Changed predictions", &self.changed);
+        print_list("Contradicted predictions", &self.contradicted);
+    }
+}
+
+fn print_list<T: fmt::Display>(title: &str, items: &[T]) {
+    if items.is_empty() {
+        return;
+    }
+    println!("{title}: {}", items.len());
+    for item in items {
+        println!("- {item}");
+    }
+}
+
+/// Returns the added and removed nodes and edges and the changed edge signs
+pub fn structural_diff(first: &Graph, second: &Graph) -> StructuralDiff {
+    let first_nodes = nodes(first);
+    let second_nodes = nodes(second);
+    let first_edges = edges(first);
+    let second_edges = edges(second);
+
+    let mut added_edges = vec![];
+    let mut sign_changes = vec![];
+    for ((start, target), signs) in &second_edges {
+        match first_edges.get(&(start.clone(), target.clone())) {
+            None => added_edges.push(SignedEdge {
+                start: start.clone(),
+                target: target.clone(),
+                signs: signs.iter().copied().collect(),
+            }),
+            Some(before) if before != signs => sign_changes.push(SignChange {
+                start: start.clone(),
+                target: target.clone(),
+                before: before.iter().copied().collect(),
+                after: signs.iter().copied().collect(),
+            }),
+            _ => {}
+        }
+    }
+    let removed_edges = first_edges
+        .iter()
+        .filter(|(key, _)| !second_edges.contains_key(key))
+        .map(|((start, target), signs)| SignedEdge {
+            start: start.clone(),
+            target: target.clone(),
+            signs: signs.iter().copied().collect(),
+        })
+        .collect();
+
+    StructuralDiff {
+        added_nodes: second_nodes.difference(&first_nodes).cloned().collect(),
+        removed_nodes: first_nodes.difference(&second_nodes).cloned().collect(),
+        added_edges,
+        removed_edges,
+        sign_changes,
+    }
+}
+
+fn nodes(graph: &Graph) -> BTreeSet<NodeId> {
+    graph
+        .or_nodes()
+        .iter()
+        .chain(graph.and_nodes())
+        .cloned()
+        .collect()
+}
+
+fn edges(graph: &Graph) -> BTreeMap<(NodeId, NodeId), BTreeSet<Sign>> {
+    let mut edges: BTreeMap<(NodeId, NodeId), BTreeSet<Sign>> = BTreeMap::new();
+    for (list, sign) in [
+        (graph.activations(), Sign::Plus),
+        (graph.inhibitions(), Sign::Minus),
+        (graph.unknowns(), Sign::Unknown),
+    ] {
+        for (start, target) in list {
+            edges
+                .entry((start.clone(), target.clone()))
+                .or_default()
+                .insert(sign);
+        }
+    }
+    edges
+}
+
+/// Compares two versions of a network with the same observations.
+///
+/// The consistency and the predictions are computed under mcos or scenfit,
+/// each network is checked with its own input facts.
+pub fn compare_networks(
+    first: &Graph,
+    first_inputs: &FactBase,
+    second: &Graph,
+    second_inputs: &FactBase,
+    profile: &FactBase,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<NetworkComparison> {
+    let structure = structural_diff(first, second);
+    let (first_consistency, first_predictions) =
+        consistency_and_predictions(&first.to_facts(), profile, first_inputs, scenfit, setting)?;
+    let (second_consistency, second_predictions) =
+        consistency_and_predictions(&second.to_facts(), profile, second_inputs, scenfit, setting)?;
+
+    let mut predictions: BTreeMap<String, (Option<Behavior>, Option<Behavior>)> = BTreeMap::new();
+    for p in first_predictions {
+        predictions.entry(p.node).or_default().0 = Some(p.behavior);
+    }
+    for p in second_predictions {
+        predictions.entry(p.node).or_default().1 = Some(p.behavior);
+    }
+    let mut gained = vec![];
+    let mut lost = vec![];
+    let mut changed = vec![];
+    let mut contradicted = vec![];
+    for (node, behaviors) in predictions {
+        match behaviors {
+            (None, Some(behavior)) => gained.push(Prediction { node, behavior }),
+            (Some(behavior), None) => lost.push(Prediction { node, behavior }),
+            (Some(first), Some(second)) if first != second => {
+                let pair = PredictionPair {
+                    node,
+                    first,
+                    second,
+                };
+                if first.is_compatible(&second) {
+                    changed.push(pair);
+                } else {
+                    contradicted.push(pair);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(NetworkComparison {
+        structure,
+        first_consistency,
+        second_consistency,
+        gained,
+        lost,
+        changed,
+        contradicted,
+    })
+}
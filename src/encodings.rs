@@ -2,9 +2,6 @@
 pub const PRG_CONTRADICTORY_OBS: &str = include_str!("encodings/contradictory_obs.lp");
 pub const PRG_GUESS_INPUTS: &str = include_str!("encodings/guess_inputs.lp");
 
-// minimal inconsistent cores
-pub const PRG_MICS: &str = include_str!("encodings/mics.lp");
-
 // basic sign consistency
 pub const PRG_SIGN_CONS: &str = include_str!("encodings/sign_cons.lp");
 
@@ -31,7 +28,9 @@ pub const PRG_MIN_ADDED_INFLUENCES: &str = include_str!("encodings/min_added_inf
 pub const PRG_REMOVE_EDGES: &str = include_str!("encodings/remove_edges.lp");
 pub const PRG_ADD_EDGES: &str = include_str!("encodings/add_edges.lp");
 pub const PRG_FLIP_EDGE_DIRECTIONS: &str = include_str!("encodings/flip_edge_directions.lp");
+pub const PRG_FLIP_EDGE_SIGNS: &str = include_str!("encodings/flip_edge_signs.lp");
 pub const PRG_MIN_WEIGHTED_REPAIRS: &str = include_str!("encodings/min_weighted_repairs.lp");
+pub const PRG_MIN_COMBINED_REPAIRS: &str = include_str!("encodings/min_combined_repairs.lp");
 
 pub const PRG_BEST_ONE_EDGE: &str = "
 % guess one edge end to add
@@ -60,6 +59,7 @@ pub const PRG_SHOW_ERRORS: &str = "
 #show flip_node_sign_0_to_Minus/2.
 #show flip_node_sign_notMinus_to_Minus/2.
 #show flip_node_sign_notPlus_to_Plus/2.
+#show flip_node_sign_change_to_0/2.
 ";
 pub const PRG_SHOW_LABELS: &str = "
 #show vlabel(X,or(V),S) : vlabel(X,or(V),S).
@@ -70,4 +70,12 @@ pub const PRG_SHOW_REPAIRS: &str = "
 #show new_influence/3.
 ";
 pub const PRG_SHOW_FLIP: &str = "#show flip/3.";
+pub const PRG_SHOW_FLIP_SIGN: &str = "#show flip_edge_sign/3.";
+pub const PRG_SHOW_COMBINED_REPAIRS: &str = "
+#show remedge(U,V,S) : remedge(U,V,S), not flip(U,V,S), not flip_edge_sign(U,V,S).
+#show addedge/3.
+#show flip/3.
+#show flip_edge_sign/3.
+#show new_influence/3.
+";
 pub const PRG_SHOW_ADD_EDGE_END: &str = "#show addeddy/1.";
@@ -0,0 +1,209 @@
+use crate::profile_parser::{Behavior, Profile};
+use crate::{NodeId, Prediction};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const BEHAVIORS: [Behavior; 6] = [
+    Behavior::Plus,
+    Behavior::Minus,
+    Behavior::Zero,
+    Behavior::NotPlus,
+    Behavior::NotMinus,
+    Behavior::Change,
+];
+
+/// Precision, recall and accuracy of the predictions of one behavior
+#[derive(Debug, Clone, Serialize)]
+pub struct BehaviorScore {
+    pub behavior: Behavior,
+    /// number of predictions with this behavior
+    pub predicted: usize,
+    /// number of reference observations with this behavior
+    pub observed: usize,
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+    pub accuracy: Option<f64>,
+}
+
+/// Number of nodes for each pair of predicted and observed behavior,
+/// the last row counts the observed nodes without prediction
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfusionMatrix {
+    pub behaviors: Vec<Behavior>,
+    pub rows: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Evaluation {
+    /// number of nodes in the reference
+    pub reference_nodes: usize,
+    /// number of reference nodes with a prediction
+    pub predicted_nodes: usize,
+    /// average match score of the predicted reference nodes
+    pub accuracy: Option<f64>,
+    pub behaviors: Vec<BehaviorScore>,
+    pub confusion_matrix: ConfusionMatrix,
+}
+impl Evaluation {
+    pub fn print(&self) {
+        println!("\n## Evaluation\n");
+        println!("- Reference nodes: {}", self.reference_nodes);
+        println!("- Predicted reference nodes: {}", self.predicted_nodes);
+        println!("- Accuracy: {}", show(self.accuracy));
+        println!("\n| behavior | predicted | observed | precision | recall | accuracy |");
+        println!("| -------- | --------- | -------- | --------- | ------ | -------- |");
+        for score in &self.behaviors {
+            println!(
+                "| {:8} | {:9} | {:8} | {:>9} | {:>6} | {:>8} |",
+                score.behavior.to_string(),
+                score.predicted,
+                score.observed,
+                show(score.precision),
+                show(score.recall),
+                show(score.accuracy)
+            );
+        }
+        println!("\n### Confusion matrix (rows: predicted, columns: observed)\n");
+        print!("|          |");
+        for b in &self.confusion_matrix.behaviors {
+            print!(" {:>8} |", b.to_string());
+        }
+        println!();
+        print!("| -------- |");
+        for _ in &self.confusion_matrix.behaviors {
+            print!(" -------- |");
+        }
+        println!();
+        for (i, row) in self.confusion_matrix.rows.iter().enumerate() {
+            let label = match self.confusion_matrix.behaviors.get(i) {
+                Some(b) => b.to_string(),
+                None => "none".to_string(),
+            };
+            print!("| {label:8} |");
+            for count in row {
+                print!(" {count:>8} |");
+            }
+            println!();
+        }
+    }
+}
+
+fn show(value: Option<f64>) -> String {
+    match value {
+        Some(v) => format!("{v:.3}"),
+        None => "-".to_string(),
+    }
+}
+
+/// Fraction of the sign changes allowed by the prediction that agree with the observation,
+/// e.g. 1 for `+` and `notMinus`, 0.5 for `notPlus` and `-`, and 0 for `+` and `-`.
+pub fn match_score(predicted: Behavior, observed: Behavior) -> f64 {
+    let signs = predicted.signs();
+    let matches = signs
+        .iter()
+        .filter(|s| observed.signs().contains(s))
+        .count();
+    matches as f64 / signs.len() as f64
+}
+
+/// Compares the predictions with a reference set of observations.
+///
+/// Nodes in `ignore`, e.g. the nodes observed in the input data, are not evaluated.
+/// Weak predictions count as partial matches, see [`match_score`].
+/// The precision of a behavior is the average match score of its predictions,
+/// the recall is the average match score of the reference nodes with this behavior
+/// (0 for nodes without prediction),
+/// and the accuracy is the fraction of predicted reference nodes where
+/// prediction and observation agree on having this behavior or not.
+pub fn evaluate_predictions(
+    predictions: &[Prediction],
+    reference: &Profile,
+    ignore: &[NodeId],
+) -> Evaluation {
+    let predicted: HashMap<&str, Behavior> = predictions
+        .iter()
+        .map(|p| (p.node.as_str(), p.behavior))
+        .collect();
+    // observed behavior and prediction of each reference node
+    let mut pairs: Vec<(Behavior, Option<Behavior>)> = vec![];
+    for observation in &reference.observations {
+        if ignore.contains(&observation.node) {
+            continue;
+        }
+        let prediction = predicted
+            .get(observation.node.to_string().as_str())
+            .copied();
+        pairs.push((observation.behavior, prediction));
+    }
+    let evaluated: Vec<(Behavior, Behavior)> = pairs
+        .iter()
+        .filter_map(|(o, p)| p.map(|p| (*o, p)))
+        .collect();
+
+    let mut behaviors = vec![];
+    for behavior in BEHAVIORS {
+        let with_prediction: Vec<f64> = evaluated
+            .iter()
+            .filter(|(_, p)| *p == behavior)
+            .map(|(o, p)| match_score(*p, *o))
+            .collect();
+        let with_observation: Vec<f64> = pairs
+            .iter()
+            .filter(|(o, _)| *o == behavior)
+            .map(|(o, p)| p.map_or(0.0, |p| match_score(p, *o)))
+            .collect();
+        let agree = evaluated
+            .iter()
+            .filter(|(o, p)| (*o == behavior) == (*p == behavior))
+            .count();
+        behaviors.push(BehaviorScore {
+            behavior,
+            predicted: with_prediction.len(),
+            observed: with_observation.len(),
+            precision: average(&with_prediction),
+            recall: average(&with_observation),
+            accuracy: ratio(agree, evaluated.len()),
+        });
+    }
+
+    let mut rows = vec![vec![0; BEHAVIORS.len()]; BEHAVIORS.len() + 1];
+    for (o, p) in &pairs {
+        let row = match p {
+            Some(p) => index(*p),
+            None => BEHAVIORS.len(),
+        };
+        rows[row][index(*o)] += 1;
+    }
+
+    let scores: Vec<f64> = evaluated.iter().map(|(o, p)| match_score(*p, *o)).collect();
+    Evaluation {
+        reference_nodes: pairs.len(),
+        predicted_nodes: evaluated.len(),
+        accuracy: average(&scores),
+        behaviors,
+        confusion_matrix: ConfusionMatrix {
+            behaviors: BEHAVIORS.to_vec(),
+            rows,
+        },
+    }
+}
+
+fn index(behavior: Behavior) -> usize {
+    BEHAVIORS.iter().position(|b| *b == behavior).unwrap()
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn ratio(count: usize, total: usize) -> Option<f64> {
+    if total == 0 {
+        None
+    } else {
+        Some(count as f64 / total as f64)
+    }
+}
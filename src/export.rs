@@ -0,0 +1,538 @@
+use crate::cif_parser::{EdgeSign, Graph};
+use crate::html::escape;
+use crate::profile_parser::{Behavior, Observation};
+use crate::sif_parser::is_complex_node;
+use crate::{NodeId, ObsELabel, Prediction, RepairOp};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Analysis results that are attached to the nodes and edges of an exported network
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    /// observed behaviors, the first observation of a node is used
+    pub observations: Vec<Observation>,
+    pub inputs: Vec<NodeId>,
+    pub predictions: Vec<Prediction>,
+    /// minimal inconsistent cores
+    pub mics: Vec<Vec<NodeId>>,
+    /// a repair set, edges that are added, removed or flipped are marked
+    pub repairs: Vec<RepairOp>,
+}
+
+/// A node of the network with its analysis results
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedNode {
+    pub id: String,
+    /// `or` for species, `and` for complex regulation
+    #[serde(rename = "type")]
+    pub node_type: &'static str,
+    pub observed: Option<Behavior>,
+    pub input: bool,
+    pub predicted: Option<Behavior>,
+    /// number of minimal inconsistent cores that contain the node
+    pub mics: usize,
+}
+
+/// An edge of the network with its repair status
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    /// `+`, `-` or `?`
+    pub sign: &'static str,
+    /// `none`, `added`, `removed`, `flipped` or `sign-flipped`
+    pub repair: &'static str,
+}
+
+/// Returns the nodes and edges of the graph with their annotations.
+///
+/// Edges added by the repair set are included.
+pub fn annotate(
+    graph: &Graph,
+    annotations: &Annotations,
+) -> (Vec<AnnotatedNode>, Vec<AnnotatedEdge>) {
+    let nodes = graph
+        .or_nodes()
+        .iter()
+        .map(|node| (node, "or"))
+        .chain(graph.and_nodes().iter().map(|node| (node, "and")))
+        .map(|(node, node_type)| {
+            let id = node.to_string();
+            AnnotatedNode {
+                observed: annotations
+                    .observations
+                    .iter()
+                    .find(|o| o.node == *node)
+                    .map(|o| o.behavior),
+                input: annotations.inputs.contains(node),
+                predicted: annotations
+                    .predictions
+                    .iter()
+                    .find(|p| p.node == id)
+                    .map(|p| p.behavior),
+                mics: annotations
+                    .mics
+                    .iter()
+                    .filter(|mic| mic.contains(node))
+                    .count(),
+                id,
+                node_type,
+            }
+        })
+        .collect();
+
+    let mut edges = vec![];
+    for (list, sign) in [
+        (graph.activations(), "+"),
+        (graph.inhibitions(), "-"),
+        (graph.unknowns(), "?"),
+    ] {
+        for (start, target) in list {
+            let is_edge = |e: &ObsELabel| e.start == *start && e.target == *target;
+            // flipping an edge also removes it in the flip encoding
+            let repair = if annotations
+                .repairs
+                .iter()
+                .any(|op| matches!(op, RepairOp::FlipEdgeDirection(e) if is_edge(e)))
+            {
+                "flipped"
+            } else if annotations
+                .repairs
+                .iter()
+                .any(|op| matches!(op, RepairOp::FlipEdgeSign(e) if is_edge(e)))
+            {
+                "sign-flipped"
+            } else if annotations
+                .repairs
+                .iter()
+                .any(|op| matches!(op, RepairOp::RemoveEdge(e) if is_edge(e)))
+            {
+                "removed"
+            } else {
+                "none"
+            };
+            edges.push((start.to_string(), target.to_string(), sign, repair));
+        }
+    }
+    for op in &annotations.repairs {
+        if let RepairOp::AddEdge(e) = op {
+            let sign = match e.sign {
+                EdgeSign::Plus => "+",
+                EdgeSign::Minus => "-",
+            };
+            edges.push((e.start.to_string(), e.target.to_string(), sign, "added"));
+        }
+    }
+    let edges = edges
+        .into_iter()
+        .enumerate()
+        .map(|(i, (source, target, sign, repair))| AnnotatedEdge {
+            id: format!("e{i}"),
+            source,
+            target,
+            sign,
+            repair,
+        })
+        .collect();
+    (nodes, edges)
+}
+
+/// Returns the network in GraphML format, e.g. for yEd or Cytoscape
+pub fn to_graphml(graph: &Graph, annotations: &Annotations) -> Result<String> {
+    let (nodes, edges) = annotate(graph, annotations);
+    let mut xml = String::new();
+    writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        xml,
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+    )?;
+    for (id, domain, name, kind) in [
+        ("type", "node", "type", "string"),
+        ("observed", "node", "observed", "string"),
+        ("input", "node", "input", "boolean"),
+        ("predicted", "node", "predicted", "string"),
+        ("mics", "node", "mics", "int"),
+        ("sign", "edge", "sign", "string"),
+        ("repair", "edge", "repair", "string"),
+    ] {
+        writeln!(
+            xml,
+            "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{name}\" attr.type=\"{kind}\"/>"
+        )?;
+    }
+    writeln!(xml, "  <graph id=\"network\" edgedefault=\"directed\">")?;
+    for node in &nodes {
+        writeln!(xml, "    <node id=\"{}\">", escape(&node.id))?;
+        writeln!(xml, "      <data key=\"type\">{}</data>", node.node_type)?;
+        if let Some(observed) = node.observed {
+            writeln!(xml, "      <data key=\"observed\">{observed}</data>")?;
+        }
+        writeln!(xml, "      <data key=\"input\">{}</data>", node.input)?;
+        if let Some(predicted) = node.predicted {
+            writeln!(xml, "      <data key=\"predicted\">{predicted}</data>")?;
+        }
+        writeln!(xml, "      <data key=\"mics\">{}</data>", node.mics)?;
+        writeln!(xml, "    </node>")?;
+    }
+    for edge in &edges {
+        writeln!(
+            xml,
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+            edge.id,
+            escape(&edge.source),
+            escape(&edge.target)
+        )?;
+        writeln!(xml, "      <data key=\"sign\">{}</data>", edge.sign)?;
+        writeln!(xml, "      <data key=\"repair\">{}</data>", edge.repair)?;
+        writeln!(xml, "    </edge>")?;
+    }
+    writeln!(xml, "  </graph>")?;
+    writeln!(xml, "</graphml>")?;
+    Ok(xml)
+}
+
+#[derive(Serialize)]
+struct CytoscapeElement<T> {
+    data: T,
+}
+#[derive(Serialize)]
+struct CytoscapeElements {
+    nodes: Vec<CytoscapeElement<AnnotatedNode>>,
+    edges: Vec<CytoscapeElement<AnnotatedEdge>>,
+}
+#[derive(Serialize)]
+struct CytoscapeGraph {
+    elements: CytoscapeElements,
+}
+
+/// Returns the network in Cytoscape.js JSON format
+pub fn to_cytoscape_json(graph: &Graph, annotations: &Annotations) -> Result<String> {
+    let (nodes, edges) = annotate(graph, annotations);
+    let graph = CytoscapeGraph {
+        elements: CytoscapeElements {
+            nodes: nodes
+                .into_iter()
+                .map(|data| CytoscapeElement { data })
+                .collect(),
+            edges: edges
+                .into_iter()
+                .map(|data| CytoscapeElement { data })
+                .collect(),
+        },
+    };
+    Ok(serde_json::to_string_pretty(&graph)?)
+}
+
+/// the members of an AND node with the signs of their edges, in the order of the node name
+fn and_members<'a>(graph: &'a Graph, node: &NodeId) -> Vec<(&'a NodeId, &'static str)> {
+    let mut members = vec![];
+    for (list, sign) in [
+        (graph.activations(), "+"),
+        (graph.inhibitions(), "-"),
+        (graph.unknowns(), "?"),
+    ] {
+        for (start, target) in list {
+            if target == node {
+                members.push((start, sign));
+            }
+        }
+    }
+    let name = node.to_string();
+    let order: Vec<&str> = name
+        .split(" & ")
+        .map(|m| m.trim_start_matches(['!', '?']))
+        .collect();
+    members.sort_by_key(|(member, _)| {
+        let member = member.to_string();
+        order
+            .iter()
+            .position(|m| *m == member)
+            .unwrap_or(order.len())
+    });
+    members
+}
+
+/// Returns the network in SIF format.
+///
+/// Edges are written as `1` (activation), `-1` (inhibition) or `?` (unknown influence).
+/// AND nodes become complex nodes `and1`, `and2`, ... with an edge from each member
+/// and an activation of the target, see [`crate::sif_parser`].
+/// Nodes without edges are written as a line with the node only.
+pub fn to_sif(graph: &Graph) -> Result<String> {
+    if let Some(node) = graph
+        .or_nodes()
+        .iter()
+        .find(|n| is_complex_node(&n.to_string()))
+    {
+        bail!("the node {node} would be read as complex node");
+    }
+    let complex: BTreeMap<&NodeId, String> = graph
+        .and_nodes()
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node, format!("and{}", i + 1)))
+        .collect();
+    let name = |node: &NodeId| match complex.get(node) {
+        Some(name) => name.clone(),
+        None => node.to_string(),
+    };
+    check_and_outputs(graph, "SIF")?;
+    let mut sif = String::new();
+    for (list, interaction) in [
+        (graph.activations(), "1"),
+        (graph.inhibitions(), "-1"),
+        (graph.unknowns(), "?"),
+    ] {
+        for (start, target) in list {
+            if matches!(start, NodeId::Or(_)) && matches!(target, NodeId::Or(_)) {
+                writeln!(sif, "{start}\t{interaction}\t{target}")?;
+            }
+        }
+    }
+    for node in graph.and_nodes() {
+        for (member, sign) in and_members(graph, node) {
+            let interaction = match sign {
+                "+" => "1",
+                "-" => "-1",
+                _ => "?",
+            };
+            writeln!(sif, "{member}\t{interaction}\t{}", name(node))?;
+        }
+        for (start, target) in graph.activations() {
+            if start == node {
+                writeln!(sif, "{}\t1\t{target}", name(node))?;
+            }
+        }
+    }
+    for node in graph.or_nodes() {
+        let isolated = !graph
+            .activations()
+            .iter()
+            .chain(graph.inhibitions())
+            .chain(graph.unknowns())
+            .any(|(start, target)| start == node || target == node);
+        if isolated {
+            writeln!(sif, "{node}")?;
+        }
+    }
+    Ok(sif)
+}
+
+/// AND nodes are conjunctions that activate their targets,
+/// other edges from AND nodes cannot be written
+fn check_and_outputs(graph: &Graph, format: &str) -> Result<()> {
+    for (start, target) in graph.inhibitions().iter().chain(graph.unknowns()) {
+        if let NodeId::And(_) = start {
+            bail!(
+                "the AND node {start} does not activate {target} and cannot be written in {format}"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// MathML condition that the node is active (or inactive for `-`)
+fn mathml_literal(node: &NodeId, sign: &str) -> String {
+    let level = if sign == "-" { 0 } else { 1 };
+    format!(
+        "<apply><eq/><ci> {} </ci><cn type=\"integer\">{level}</cn></apply>",
+        escape(&node.to_string())
+    )
+}
+
+/// Returns the network in SBML-qual format.
+///
+/// Each OR node becomes a Boolean qualitative species named by the node,
+/// each node with regulators the output of a transition whose inputs carry the signs of the edges.
+/// If a node is regulated by AND nodes, every regulator with a known sign becomes a function term,
+/// AND nodes as conjunction of their members, see [`crate::sbml_parser`].
+/// AND nodes with an unknown influence and AND nodes that do not activate their target cannot be written.
+pub fn to_sbml_qual(graph: &Graph) -> Result<String> {
+    check_and_outputs(graph, "SBML-qual")?;
+    let mut xml = String::new();
+    writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        xml,
+        "<sbml xmlns=\"http://www.sbml.org/sbml/level3/version1/core\" level=\"3\" version=\"1\" xmlns:qual=\"http://www.sbml.org/sbml/level3/version1/qual/version1\" qual:required=\"true\">"
+    )?;
+    writeln!(xml, "  <model id=\"network\">")?;
+    writeln!(xml, "    <listOfCompartments>")?;
+    writeln!(xml, "      <compartment id=\"default\" constant=\"true\"/>")?;
+    writeln!(xml, "    </listOfCompartments>")?;
+    writeln!(xml, "    <qual:listOfQualitativeSpecies>")?;
+    for node in graph.or_nodes() {
+        writeln!(
+            xml,
+            "      <qual:qualitativeSpecies qual:id=\"{}\" qual:compartment=\"default\" qual:constant=\"false\" qual:maxLevel=\"1\"/>",
+            escape(&node.to_string())
+        )?;
+    }
+    writeln!(xml, "    </qual:listOfQualitativeSpecies>")?;
+    writeln!(xml, "    <qual:listOfTransitions>")?;
+    let mut count = 0;
+    for target in graph.or_nodes() {
+        let mut singles = vec![];
+        let mut ands = vec![];
+        for (list, sign) in [
+            (graph.activations(), "+"),
+            (graph.inhibitions(), "-"),
+            (graph.unknowns(), "?"),
+        ] {
+            for (start, t) in list {
+                if t == target {
+                    match start {
+                        NodeId::Or(_) => singles.push((start, sign)),
+                        NodeId::And(_) => ands.push(start),
+                    }
+                }
+            }
+        }
+        if singles.is_empty() && ands.is_empty() {
+            continue;
+        }
+        count += 1;
+        let mut inputs = singles.clone();
+        let mut terms = vec![];
+        for node in &ands {
+            let members = and_members(graph, node);
+            if members.iter().any(|(_, sign)| *sign == "?") {
+                bail!(
+                    "the AND node {node} with an unknown influence cannot be written in SBML-qual"
+                );
+            }
+            let literals: Vec<String> = members
+                .iter()
+                .map(|(member, sign)| mathml_literal(member, sign))
+                .collect();
+            terms.push(format!("<apply><and/>{}</apply>", literals.join("")));
+            for member in members {
+                if !inputs.contains(&member) {
+                    inputs.push(member);
+                }
+            }
+        }
+        writeln!(xml, "      <qual:transition qual:id=\"tr{count}\">")?;
+        writeln!(xml, "        <qual:listOfInputs>")?;
+        for (i, (node, sign)) in inputs.iter().enumerate() {
+            let sign = match *sign {
+                "+" => "positive",
+                "-" => "negative",
+                _ => "unknown",
+            };
+            writeln!(
+                xml,
+                "          <qual:input qual:id=\"tr{count}_in{}\" qual:qualitativeSpecies=\"{}\" qual:sign=\"{sign}\" qual:transitionEffect=\"none\"/>",
+                i + 1,
+                escape(&node.to_string())
+            )?;
+        }
+        writeln!(xml, "        </qual:listOfInputs>")?;
+        writeln!(xml, "        <qual:listOfOutputs>")?;
+        writeln!(
+            xml,
+            "          <qual:output qual:id=\"tr{count}_out\" qual:qualitativeSpecies=\"{}\" qual:transitionEffect=\"assignmentLevel\"/>",
+            escape(&target.to_string())
+        )?;
+        writeln!(xml, "        </qual:listOfOutputs>")?;
+        if !ands.is_empty() {
+            // the unknown influences remain inputs without function term
+            let single_terms = singles
+                .iter()
+                .filter(|(_, sign)| *sign != "?")
+                .map(|(node, sign)| mathml_literal(node, sign));
+            writeln!(xml, "        <qual:listOfFunctionTerms>")?;
+            writeln!(xml, "          <qual:defaultTerm qual:resultLevel=\"0\"/>")?;
+            for term in single_terms.chain(terms) {
+                writeln!(xml, "          <qual:functionTerm qual:resultLevel=\"1\">")?;
+                writeln!(
+                    xml,
+                    "            <math xmlns=\"http://www.w3.org/1998/Math/MathML\">{term}</math>"
+                )?;
+                writeln!(xml, "          </qual:functionTerm>")?;
+            }
+            writeln!(xml, "        </qual:listOfFunctionTerms>")?;
+        }
+        writeln!(xml, "      </qual:transition>")?;
+    }
+    writeln!(xml, "    </qual:listOfTransitions>")?;
+    writeln!(xml, "  </model>")?;
+    writeln!(xml, "</sbml>")?;
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cif_parser::parse_statement;
+    use crate::{sbml_parser, sif_parser};
+    use std::fs::File;
+
+    fn network() -> Graph {
+        let statements = ["a & !b -> c", "?d -> c", "b -> e", "!e -> a"]
+            .iter()
+            .map(|line| parse_statement(line).unwrap());
+        let mut graph = Graph::from_statements(statements);
+        graph.add_node(&NodeId::Or("f".to_string()));
+        graph
+    }
+
+    fn sorted(edges: &[(NodeId, NodeId)]) -> Vec<(NodeId, NodeId)> {
+        let mut edges = edges.to_vec();
+        edges.sort();
+        edges
+    }
+
+    fn assert_same(read: &Graph, graph: &Graph) {
+        assert_eq!(read.or_nodes(), graph.or_nodes());
+        assert_eq!(read.and_nodes(), graph.and_nodes());
+        assert_eq!(sorted(read.activations()), sorted(graph.activations()));
+        assert_eq!(sorted(read.inhibitions()), sorted(graph.inhibitions()));
+        assert_eq!(sorted(read.unknowns()), sorted(graph.unknowns()));
+    }
+
+    fn read_back(
+        content: &str,
+        name: &str,
+        read: impl Fn(&File) -> Result<(Graph, Vec<String>)>,
+    ) -> (Graph, Vec<String>) {
+        let path = std::env::temp_dir().join(format!("iggy-{}-{name}", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let result = read(&File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn sif_round_trip() {
+        let graph = network();
+        let (read, warnings) = read_back(&to_sif(&graph).unwrap(), "network.sif", sif_parser::read);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_same(&read, &graph);
+    }
+
+    #[test]
+    fn sbml_qual_round_trip() {
+        let graph = network();
+        let (read, warnings) = read_back(
+            &to_sbml_qual(&graph).unwrap(),
+            "network.sbml",
+            sbml_parser::read,
+        );
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_same(&read, &graph);
+    }
+
+    #[test]
+    fn and_node_without_activation_is_rejected() {
+        let mut graph = network();
+        let and = graph.and_nodes()[0].clone();
+        let target = NodeId::Or("c".to_string());
+        graph.remove_edge(&and, &target);
+        graph.add_edge(&and, &target, EdgeSign::Minus);
+        assert!(to_sif(&graph).is_err());
+        assert!(to_sbml_qual(&graph).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::cif_parser::Graph;
+use crate::profile_parser::Behavior;
+use crate::{NodeId, Prediction};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { border-bottom: 2px solid #444; }
+h2 { border-bottom: 1px solid #aaa; margin-top: 1.5em; }
+table { border-collapse: collapse; margin: 0.5em 0; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+thead th { cursor: pointer; }
+th.sorted-asc::after { content: ' \\25b2'; }
+th.sorted-desc::after { content: ' \\25bc'; }
+.legend span { display: inline-block; padding: 0.1em 0.5em; margin-right: 0.5em; border: 1px solid #888; }
+svg { border: 1px solid #ccc; background: #fff; }
+";
+
+const SCRIPT: &str = "
+document.querySelectorAll('table').forEach(function (table) {
+  var headers = table.querySelectorAll(':scope > thead > tr > th');
+  headers.forEach(function (th, column) {
+    th.addEventListener('click', function () {
+      var rows = Array.from(table.querySelectorAll(':scope > tbody > tr'));
+      var asc = !th.classList.contains('sorted-asc');
+      headers.forEach(function (h) { h.classList.remove('sorted-asc', 'sorted-desc'); });
+      th.classList.add(asc ? 'sorted-asc' : 'sorted-desc');
+      rows.sort(function (a, b) {
+        var x = a.children[column].textContent, y = b.children[column].textContent;
+        var nx = parseFloat(x), ny = parseFloat(y);
+        var c = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+        return asc ? c : -c;
+      });
+      rows.forEach(function (row) { table.tBodies[0].appendChild(row); });
+    });
+  });
+});
+";
+
+/// colors of the node behaviors in the network drawing
+const COLORS: [(Behavior, &str); 6] = [
+    (Behavior::Plus, "#4caf50"),
+    (Behavior::Minus, "#e53935"),
+    (Behavior::Zero, "#9e9e9e"),
+    (Behavior::NotPlus, "#ffab91"),
+    (Behavior::NotMinus, "#a5d6a7"),
+    (Behavior::Change, "#ffeb3b"),
+];
+const UNKNOWN_COLOR: &str = "#ffffff";
+
+/// Graphs with more nodes are drawn on a circle instead of a force directed layout
+const MAX_FORCE_LAYOUT_NODES: usize = 500;
+
+/// A self-contained HTML report that can be viewed offline.
+///
+/// The sections are rendered from the same serializable results as the JSON output,
+/// tables of records can be sorted by clicking on a column header.
+#[derive(Debug, Clone)]
+pub struct HtmlReport {
+    title: String,
+    body: String,
+}
+impl HtmlReport {
+    pub fn new(title: &str) -> HtmlReport {
+        HtmlReport {
+            title: title.to_string(),
+            body: String::new(),
+        }
+    }
+
+    /// Adds a section with a serializable result
+    pub fn add<T: Serialize + ?Sized>(&mut self, title: &str, result: &T) -> Result<()> {
+        let value = serde_json::to_value(result)?;
+        write!(
+            self.body,
+            "<h2>{}</h2>\n{}\n",
+            escape(title),
+            render(&value)
+        )?;
+        Ok(())
+    }
+
+    /// Adds a drawing of the network, the nodes are colored by their predicted behavior
+    pub fn add_network(&mut self, graph: &Graph, predictions: &[Prediction]) -> Result<()> {
+        let behaviors: HashMap<&str, Behavior> = predictions
+            .iter()
+            .map(|p| (p.node.as_str(), p.behavior))
+            .collect();
+        let nodes: Vec<&NodeId> = graph.or_nodes().iter().chain(graph.and_nodes()).collect();
+        let index: BTreeMap<&NodeId, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        let mut edges = vec![];
+        for (list, color) in [
+            (graph.activations(), "#4caf50"),
+            (graph.inhibitions(), "#e53935"),
+            (graph.unknowns(), "#9e9e9e"),
+        ] {
+            for (s, t) in list {
+                if let (Some(s), Some(t)) = (index.get(s), index.get(t)) {
+                    edges.push((*s, *t, color));
+                }
+            }
+        }
+        let positions = layout(nodes.len(), &edges);
+        let size = 800.0;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"-20 -20 {} {}\">",
+            size + 40.0,
+            size + 40.0
+        )?;
+        for (s, t, color) in &edges {
+            let (x1, y1) = positions[*s];
+            let (x2, y2) = positions[*t];
+            writeln!(
+                svg,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{color}\" stroke-opacity=\"0.6\"/>",
+                x1 * size,
+                y1 * size,
+                x2 * size,
+                y2 * size
+            )?;
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            let (x, y) = positions[i];
+            let (name, radius) = match node {
+                NodeId::Or(name) => (name, 6.0),
+                NodeId::And(name) => (name, 3.0),
+            };
+            let behavior = behaviors.get(name.as_str()).copied();
+            let color = behavior
+                .and_then(|b| COLORS.iter().find(|(c, _)| *c == b))
+                .map_or(UNKNOWN_COLOR, |(_, color)| color);
+            let label = match behavior {
+                Some(b) => format!("{name} = {b}"),
+                None => name.to_string(),
+            };
+            writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{radius}\" fill=\"{color}\" stroke=\"#444\"><title>{}</title></circle>",
+                x * size,
+                y * size,
+                escape(&label)
+            )?;
+        }
+        svg.push_str("</svg>");
+
+        let mut legend = String::from("<p class=\"legend\">");
+        for (behavior, color) in COLORS {
+            write!(
+                legend,
+                "<span style=\"background:{color}\">{}</span>",
+                escape(&behavior.to_string())
+            )?;
+        }
+        write!(
+            legend,
+            "<span style=\"background:{UNKNOWN_COLOR}\">no prediction</span></p>"
+        )?;
+        write!(self.body, "<h2>Network</h2>\n{legend}\n{svg}\n")?;
+        Ok(())
+    }
+
+    pub fn to_html(&self) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n{}<script>{SCRIPT}</script>\n</body>\n</html>\n",
+            self.body,
+            title = escape(&self.title)
+        )
+    }
+}
+
+/// renders a JSON value as HTML, arrays of objects become tables
+fn render(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => escape(s),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Object(map) => {
+            let mut html = String::from("<table>\n<tbody>\n");
+            for (key, value) in map {
+                html.push_str(&format!(
+                    "<tr><th>{}</th><td>{}</td></tr>\n",
+                    escape(key),
+                    render(value)
+                ));
+            }
+            html.push_str("</tbody>\n</table>");
+            html
+        }
+        Value::Array(items) if items.is_empty() => "none".to_string(),
+        Value::Array(items) if items.iter().all(|i| i.is_object()) => {
+            let mut columns: Vec<&String> = vec![];
+            for item in items {
+                for key in item.as_object().into_iter().flat_map(|o| o.keys()) {
+                    if !columns.contains(&key) {
+                        columns.push(key);
+                    }
+                }
+            }
+            let mut html = String::from("<table>\n<thead><tr>");
+            for column in &columns {
+                html.push_str(&format!("<th>{}</th>", escape(column)));
+            }
+            html.push_str("</tr></thead>\n<tbody>\n");
+            for item in items {
+                html.push_str("<tr>");
+                for column in &columns {
+                    let cell = item.get(column.as_str()).map_or(String::new(), render);
+                    html.push_str(&format!("<td>{cell}</td>"));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</tbody>\n</table>");
+            html
+        }
+        Value::Array(items) => {
+            let mut html = String::from("<ul>\n");
+            for item in items {
+                html.push_str(&format!("<li>{}</li>\n", render(item)));
+            }
+            html.push_str("</ul>");
+            html
+        }
+    }
+}
+
+/// escapes special characters in HTML and XML text
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Computes node positions in the unit square.
+///
+/// Small graphs get a force directed layout (Fruchterman-Reingold) starting from a circle,
+/// large graphs are drawn on the circle.
+fn layout(n: usize, edges: &[(usize, usize, &str)]) -> Vec<(f64, f64)> {
+    let mut positions: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / n.max(1) as f64;
+            (0.5 + 0.45 * angle.cos(), 0.5 + 0.45 * angle.sin())
+        })
+        .collect();
+    if !(2..=MAX_FORCE_LAYOUT_NODES).contains(&n) {
+        return positions;
+    }
+    let k = (1.0 / n as f64).sqrt();
+    let iterations = 200;
+    for iteration in 0..iterations {
+        let temperature = 0.1 * (1.0 - iteration as f64 / iterations as f64);
+        let mut shifts = vec![(0.0, 0.0); n];
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let dx = positions[i].0 - positions[j].0;
+                let dy = positions[i].1 - positions[j].1;
+                let distance = (dx * dx + dy * dy).sqrt().max(1e-6);
+                let force = k * k / distance;
+                shifts[i].0 += dx / distance * force;
+                shifts[i].1 += dy / distance * force;
+            }
+        }
+        for (s, t, _) in edges {
+            let dx = positions[*s].0 - positions[*t].0;
+            let dy = positions[*s].1 - positions[*t].1;
+            let distance = (dx * dx + dy * dy).sqrt().max(1e-6);
+            let force = distance * distance / k;
+            shifts[*s].0 -= dx / distance * force;
+            shifts[*s].1 -= dy / distance * force;
+            shifts[*t].0 += dx / distance * force;
+            shifts[*t].1 += dy / distance * force;
+        }
+        for (position, (dx, dy)) in positions.iter_mut().zip(shifts) {
+            let length = (dx * dx + dy * dy).sqrt().max(1e-6);
+            let step = length.min(temperature);
+            position.0 = (position.0 + dx / length * step).clamp(0.0, 1.0);
+            position.1 = (position.1 + dy / length * step).clamp(0.0, 1.0);
+        }
+    }
+    positions
+}
@@ -0,0 +1,187 @@
+use crate::cif_parser::Graph;
+use crate::profile_parser::{Behavior, Profile, ProfileId};
+use crate::{input_nodes, NodeId};
+use anyhow::{bail, Result};
+use clingo::{ClingoError, FactBase, Symbol, ToSymbol};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Inputs for all experiments, given in a separate file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InputSpec {
+    /// nodes declared as input
+    pub inputs: Vec<NodeId>,
+    /// nodes that are removed from the computed inputs
+    pub excluded: Vec<NodeId>,
+}
+
+#[derive(ToSymbol)]
+struct Input<'a> {
+    node: &'a NodeId,
+}
+
+/// An input that is doubtful in an experiment
+#[derive(Debug, Clone, Serialize)]
+pub struct InputConflict {
+    pub node: NodeId,
+    /// the experiment, `None` for conflicts in all experiments
+    pub profile: Option<ProfileId>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputReport {
+    pub declared: usize,
+    pub excluded: usize,
+    /// the inputs of all experiments after merging the declared and the computed inputs
+    pub inputs: Vec<NodeId>,
+    pub conflicts: Vec<InputConflict>,
+}
+impl InputReport {
+    pub fn print(&self) {
+        println!("\n## Inputs\n");
+        println!("- Declared inputs: {}", self.declared);
+        println!("- Excluded inputs: {}", self.excluded);
+        println!("- Inputs of all experiments: {}", self.inputs.len());
+        for node in &self.inputs {
+            println!("  - {node}");
+        }
+        println!("- Conflicts: {}", self.conflicts.len());
+        for conflict in &self.conflicts {
+            match &conflict.profile {
+                Some(profile) => {
+                    println!("  - {} in {profile}: {}", conflict.node, conflict.reason)
+                }
+                None => println!("  - {}: {}", conflict.node, conflict.reason),
+            }
+        }
+    }
+}
+
+/// Reads an inputs file
+pub fn read(file: &File) -> Result<InputSpec> {
+    read_renamed(file, |name| name.to_string())
+}
+
+/// Reads an inputs file and renames its nodes, e.g. to resolve aliases.
+///
+/// Each line contains a node that is declared as input, or `!node` to exclude the node
+/// from the computed inputs. Lines starting with `#` are comments.
+pub fn read_renamed(file: &File, mut rename: impl FnMut(&str) -> String) -> Result<InputSpec> {
+    let mut spec = InputSpec::default();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (nodes, name) = match line.strip_prefix('!') {
+            Some(name) => (&mut spec.excluded, name.trim()),
+            None => (&mut spec.inputs, line),
+        };
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            bail!("line {}: expected a node, found '{line}'", i + 1);
+        }
+        let node = NodeId::Or(rename(name));
+        if !nodes.contains(&node) {
+            nodes.push(node);
+        }
+    }
+    Ok(spec)
+}
+
+impl InputSpec {
+    /// Returns the input facts of the computed inputs without the excluded nodes
+    /// and with the declared inputs
+    pub fn merge(&self, computed: &FactBase) -> Result<FactBase> {
+        let excluded = self
+            .excluded
+            .iter()
+            .map(|node| node.symbol())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut inputs = FactBase::new();
+        for input in computed.iter() {
+            if !excluded.contains(&input.arguments()?[0]) {
+                inputs.insert(input);
+            }
+        }
+        for node in &self.inputs {
+            inputs.insert(&Input { node });
+        }
+        Ok(inputs)
+    }
+}
+
+/// Checks the inputs of the experiments.
+///
+/// Reports inputs that are not in the network, inputs that are observed as unchanged,
+/// and nodes that are excluded but declared as input in the file or in an experiment.
+/// Inputs that an experiment declares itself may be unchanged,
+/// e.g. the stimuli that a MIDAS treatment does not apply.
+pub fn check_inputs(
+    graph: &Graph,
+    profiles: &[Profile],
+    inputs: &FactBase,
+    spec: &InputSpec,
+) -> Result<InputReport> {
+    let mut inputs = input_nodes(inputs)?;
+    inputs.sort();
+    inputs.dedup();
+    let mut conflicts = vec![];
+    for node in &spec.excluded {
+        if spec.inputs.contains(node) {
+            conflicts.push(InputConflict {
+                node: node.clone(),
+                profile: None,
+                reason: "declared and excluded in the inputs file".to_string(),
+            });
+        }
+    }
+    for node in &inputs {
+        if !graph.or_nodes().contains(node) {
+            conflicts.push(InputConflict {
+                node: node.clone(),
+                profile: None,
+                reason: "not in the network".to_string(),
+            });
+        }
+    }
+    for profile in profiles {
+        for node in &profile.inputs {
+            if spec.excluded.contains(node) {
+                conflicts.push(InputConflict {
+                    node: node.clone(),
+                    profile: Some(profile.id().clone()),
+                    reason: "excluded, but declared as input in the observations".to_string(),
+                });
+            }
+            if !inputs.contains(node) && !graph.or_nodes().contains(node) {
+                conflicts.push(InputConflict {
+                    node: node.clone(),
+                    profile: Some(profile.id().clone()),
+                    reason: "not in the network".to_string(),
+                });
+            }
+        }
+        for observation in profile.all_observations() {
+            let node = &observation.node;
+            if observation.behavior == Behavior::Zero
+                && inputs.contains(node)
+                && !profile.inputs.contains(node)
+            {
+                conflicts.push(InputConflict {
+                    node: node.clone(),
+                    profile: Some(profile.id().clone()),
+                    reason: "input observed as unchanged".to_string(),
+                });
+            }
+        }
+    }
+    Ok(InputReport {
+        declared: spec.inputs.len(),
+        excluded: spec.excluded.len(),
+        inputs,
+        conflicts,
+    })
+}
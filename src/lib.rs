@@ -1,24 +1,53 @@
+pub mod aliases;
+pub mod bnet_parser;
+pub mod boolean;
 pub mod cif_parser;
+pub mod components;
+pub mod crossvalidation;
+pub mod design;
+pub mod diff;
+pub mod evaluation;
+pub mod export;
+pub mod html;
+pub mod inputs;
+pub mod lint;
+pub mod midas_parser;
+pub mod perturbation;
 use cif_parser::EdgeSign;
 pub mod profile_parser;
+pub mod pruning;
+pub mod replicates;
+pub mod sbml_parser;
+pub mod sif_parser;
+pub mod timeseries;
 use clingo::{
-    ast::Location, defaults::Non, AllModels, ClingoError, Control, ExternalError, FactBase,
-    FunctionHandler, GenericControl, GenericSolveHandle, OptimalModels, Part, ShowType, SolveMode,
-    Symbol, SymbolType, ToSymbol,
+    ast::{Location, Statement, StatementHandler, StatementIsA},
+    defaults::Non,
+    ClingoError, Control, ExternalError, FactBase, FunctionHandler, GenericControl,
+    GenericSolveHandle, Model, Part, ShowType, Signature, SolveMode, SolveResult, SolverLiteral,
+    Statistics, StatisticsType, Symbol, SymbolType, ToSymbol,
 };
 use profile_parser::{Behavior, ProfileId};
 
 /// This module contains the queries which can be asked to the model and data.
 pub mod encodings;
-use anyhow::Result;
+use aliases::{AliasReport, Aliases};
+use anyhow::{anyhow, bail, Context, Result};
+use cif_parser::Graph;
 use encodings::*;
-use log::info;
+use log::{info, warn};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
-type ControlWithFH = GenericControl<Non, Non, Non, MemberFH>;
 type SolveHandleWithFH<FH> = GenericSolveHandle<Non, Non, Non, FH, Non>;
+type ControlWithFH<FH> = GenericControl<Non, Non, Non, FH>;
 
 type Labelings = Vec<Prediction>;
 
@@ -28,16 +57,24 @@ pub struct Setting {
     pub ep: bool,
     pub fp: bool,
     pub fc: bool,
+    /// additional programs with domain knowledge
+    pub user_programs: Vec<UserProgram>,
 }
 impl Setting {
     pub fn to_json(&self) -> String {
+        let user_programs: Vec<&str> = self.user_programs.iter().map(|p| &p.name[..]).collect();
         format!(
             "{{
         \"depmat\":{},
         \"elempath\":{},
         \"forward-propagation\":{},
-        \"founded-constraints\":{}\n}}",
-            !self.os, self.ep, self.fp, self.fc
+        \"founded-constraints\":{},
+        \"user-programs\":{}\n}}",
+            !self.os,
+            self.ep,
+            self.fp,
+            self.fc,
+            serde_json::to_string(&user_programs).unwrap()
         )
     }
 }
@@ -69,9 +106,263 @@ impl fmt::Display for Setting {
                 writeln!(f, "- All observed changes must be explained by an input.")?;
             }
         }
+        for program in &self.user_programs {
+            writeln!(f, "- User constraints from {}.", program.name)?;
+        }
         write!(f, "")
     }
 }
+
+/// Predicates shown by the queries, user programs must not derive them
+const SHOWN_PREDICATES: &[&str] = &[
+    "pred",
+    "vlabel",
+    "active",
+    "remedge",
+    "addedge",
+    "addeddy",
+    "new_influence",
+    "flip",
+    "flip_edge_sign",
+    "flip_node_sign_Plus_to_0",
+    "flip_node_sign_Plus_to_Minus",
+    "flip_node_sign_Minus_to_0",
+    "flip_node_sign_Minus_to_Plus",
+    "flip_node_sign_0_to_Plus",
+    "flip_node_sign_0_to_Minus",
+    "flip_node_sign_notMinus_to_Minus",
+    "flip_node_sign_notPlus_to_Plus",
+    "flip_node_sign_change_to_0",
+];
+
+/// A user supplied logic program that is added to the consistency, labeling, prediction and
+/// repair queries.
+///
+/// The program can refer to the following predicates of the encodings:
+///
+/// + `vertex(V)` - node `V` of the network, `or("name")` for species and `and("name")` for
+///   complex nodes
+/// + `edge(U,V)`, `obs_e_label(U,V,S)` - edge from `U` to `V` with sign `S` (`1` or `-1`)
+/// + `elabel(U,V,S)` - sign `S` of the edge from `U` to `V` in a labeling
+/// + `exp(E)` - experiment (observation profile) `E`
+/// + `obs_v_label(E,V,S)` - observed behavior `S` (`1`, `-1`, `0`, `notPlus`, `notMinus`,
+///   `change`) of node `V` in experiment `E`
+/// + `input(E,V)` - node `V` is an input in experiment `E`
+/// + `is_min(E,V)`, `is_max(E,V)` - initial level of node `V` in experiment `E`
+/// + `vlabel(E,V,S)` - behavior `S` (`1`, `-1`, `0`) of node `V` in experiment `E`
+/// + `remedge(U,V,S)`, `addedge(U,V,S)`, `flip(U,V,S)`, `flip_edge_sign(U,V,S)`,
+///   `new_influence(E,V,S)` - repair operations
+///
+/// For example, the constraint
+/// `:- vlabel(E,or("mtor"),-1), vlabel(E,or("s6k"),1).`
+/// forbids an increase of `s6k` when `mtor` decreases.
+///
+/// User programs must not contain `#show` statements and must not derive predicates that are
+/// part of the output of the queries.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserProgram {
+    pub name: String,
+    #[serde(skip)]
+    program: String,
+}
+impl UserProgram {
+    pub fn new(name: &str, program: &str) -> Result<UserProgram> {
+        let mut checker = UserProgramChecker { errors: vec![] };
+        clingo::ast::parse_string_with_statement_handler(program, &mut checker)
+            .map_err(|e| anyhow!("unable to parse user program {name}: {e}"))?;
+        if !checker.errors.is_empty() {
+            return Err(anyhow!(
+                "invalid user program {name}:\n- {}",
+                checker.errors.join("\n- ")
+            ));
+        }
+        // errors like unsafe variables are only found when grounding
+        let mut ctl = clingo::control(vec![])?;
+        ctl.add("base", &[], program)
+            .map_err(|e| anyhow!("invalid user program {name}: {e}"))?;
+        let mut ctl = ctl.register_function_handler(MemberFH);
+        ctl.ground(&[Part::new("base", vec![])?])
+            .map_err(|e| anyhow!("unable to ground user program {name}: {e}"))?;
+        Ok(UserProgram {
+            name: name.to_string(),
+            program: program.to_string(),
+        })
+    }
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+}
+struct UserProgramChecker {
+    errors: Vec<String>,
+}
+impl StatementHandler for UserProgramChecker {
+    fn on_statement(&mut self, stm: &Statement) -> bool {
+        let text = stm.to_string().unwrap_or_default();
+        match stm.clone().is_a() {
+            Ok(StatementIsA::ShowSignature(_)) | Ok(StatementIsA::ShowTerm(_)) => {
+                self.errors
+                    .push(format!("#show statements are not allowed: {text}"));
+            }
+            Ok(StatementIsA::Program(_)) if text != "#program base." => {
+                self.errors
+                    .push(format!("#program statements are not allowed: {text}"));
+            }
+            Ok(StatementIsA::Rule(rule)) => {
+                let head = rule.head().to_string().unwrap_or_default();
+                for name in SHOWN_PREDICATES {
+                    if uses_predicate(&head, name) {
+                        self.errors
+                            .push(format!("predicate {name} must not be derived: {text}"));
+                    }
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+}
+/// check whether the text contains an atom of the predicate
+fn uses_predicate(text: &str, name: &str) -> bool {
+    text.match_indices(name).any(|(i, _)| {
+        let before = text[..i].chars().last();
+        let after = text[i + name.len()..].chars().next();
+        !matches!(before, Some(c) if c.is_alphanumeric() || c == '_' || c == '"')
+            && matches!(after, Some('('))
+    })
+}
+fn add_user_programs(ctl: &mut Control, setting: &Setting) -> Result<()> {
+    for program in &setting.user_programs {
+        ctl.add("base", &[], &program.program)?;
+    }
+    Ok(())
+}
+/// Grounding and solving statistics of a single query
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SolverStatistics {
+    pub query: String,
+    pub atoms: u64,
+    pub rules: u64,
+    pub choices: u64,
+    pub conflicts: u64,
+    pub models: u64,
+    /// grounding time in seconds
+    pub ground_time: f64,
+    /// solving time in seconds
+    pub solve_time: f64,
+    /// costs of the intermediate models of an optimization
+    pub costs: Vec<Vec<i64>>,
+}
+impl SolverStatistics {
+    pub fn print(&self) {
+        println!("\n- {}", self.query);
+        println!("  - Atoms:       {}", self.atoms);
+        println!("  - Rules:       {}", self.rules);
+        println!("  - Choices:     {}", self.choices);
+        println!("  - Conflicts:   {}", self.conflicts);
+        println!("  - Models:      {}", self.models);
+        println!("  - Grounding:   {:.3}s", self.ground_time);
+        println!("  - Solving:     {:.3}s", self.solve_time);
+        if !self.costs.is_empty() {
+            let costs: Vec<String> = self.costs.iter().map(|c| format!("{c:?}")).collect();
+            println!("  - Costs:       {}", costs.join(" "));
+        }
+    }
+}
+
+/// Progress of a running query
+#[derive(Debug, Clone, Serialize)]
+pub enum Progress {
+    /// grounding of the query has finished
+    Grounded { query: String, time: f64 },
+    /// the solver found a model, the cost is empty if the query does not optimize
+    Model {
+        query: String,
+        number: u64,
+        cost: Vec<i64>,
+        optimal: bool,
+    },
+    /// the query has finished
+    Finished(SolverStatistics),
+}
+
+type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+static PROGRESS_CALLBACK: Mutex<Option<ProgressCallback>> = Mutex::new(None);
+
+/// Register a callback that is called with the progress of every query.
+///
+/// Solver statistics are only collected while a callback is registered.
+/// The callback may be called from several threads at once.
+pub fn set_progress_callback<F: Fn(&Progress) + Send + Sync + 'static>(callback: F) {
+    *PROGRESS_CALLBACK.lock().unwrap() = Some(Arc::new(callback));
+}
+
+/// Remove the progress callback
+pub fn clear_progress_callback() {
+    *PROGRESS_CALLBACK.lock().unwrap() = None;
+}
+
+fn progress_enabled() -> bool {
+    PROGRESS_CALLBACK.lock().unwrap().is_some()
+}
+
+fn report_progress(progress: Progress) {
+    // the lock is released before the call, so the callback can run queries itself
+    let callback = PROGRESS_CALLBACK.lock().unwrap().clone();
+    if let Some(callback) = callback {
+        callback(&progress);
+    }
+}
+
+/// Collects the statistics of all queries and logs their progress
+#[derive(Debug, Clone, Default)]
+pub struct StatisticsCollector {
+    statistics: Arc<Mutex<Vec<SolverStatistics>>>,
+}
+impl StatisticsCollector {
+    /// Registers a progress callback that logs the progress and collects the statistics
+    pub fn register() -> StatisticsCollector {
+        let collector = StatisticsCollector::default();
+        let collected = Arc::clone(&collector.statistics);
+        set_progress_callback(move |progress| match progress {
+            Progress::Grounded { query, time } => info!("{query}: grounded in {time:.3}s"),
+            Progress::Model {
+                query,
+                number,
+                cost,
+                optimal,
+            } => {
+                if cost.is_empty() {
+                    info!("{query}: model {number}");
+                } else if *optimal {
+                    info!("{query}: model {number} with optimal cost {cost:?}");
+                } else {
+                    info!("{query}: model {number} with cost {cost:?}");
+                }
+            }
+            Progress::Finished(stats) => collected.lock().unwrap().push(stats.clone()),
+        });
+        collector
+    }
+    /// Returns the statistics of the finished queries
+    pub fn statistics(&self) -> Vec<SolverStatistics> {
+        self.statistics.lock().unwrap().clone()
+    }
+    /// Prints the statistics of the finished queries
+    pub fn print(&self, json: bool) -> Result<()> {
+        let statistics = self.statistics();
+        if json {
+            let serialized = serde_json::to_string(&statistics)?;
+            println!(",\"Solver statistics\":{serialized}");
+        } else {
+            println!("\n## Solver statistics");
+            for stats in &statistics {
+                stats.print();
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("IggyError: {msg}")]
 pub struct IggyError {
@@ -121,6 +412,8 @@ pub enum RepairOp {
     AddEdge(ObsELabel),
     RemoveEdge(ObsELabel),
     FlipEdgeDirection(ObsELabel),
+    /// the edge with its original sign
+    FlipEdgeSign(ObsELabel),
     FlipNodeSign {
         profile: ProfileId,
         node: NodeId,
@@ -138,6 +431,7 @@ impl fmt::Display for RepairOp {
             RepairOp::AddEdge(e) => write!(f, "add edge: {e}"),
             RepairOp::RemoveEdge(e) => write!(f, "remove edge: {e}"),
             RepairOp::FlipEdgeDirection(e) => write!(f, "flip direction: {e}"),
+            RepairOp::FlipEdgeSign(e) => write!(f, "flip sign: {e}"),
             RepairOp::FlipNodeSign {
                 profile: _,
                 node,
@@ -190,6 +484,13 @@ impl fmt::Display for RepairOp {
             } => {
                 write!(f, "flip {node}: notMinus to -")
             }
+            RepairOp::FlipNodeSign {
+                profile: _,
+                node,
+                direction: Direction::ChangeToZero,
+            } => {
+                write!(f, "flip {node}: CHANGE to 0")
+            }
             RepairOp::NewInfluence {
                 profile: _,
                 target,
@@ -208,6 +509,194 @@ impl fmt::Display for RepairOp {
     }
 }
 
+/// Costs of the repair operations of the combined repair mode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepairCosts {
+    pub remove_edge: u32,
+    pub flip_direction: u32,
+    pub flip_sign: u32,
+    pub add_edge: u32,
+    pub new_influence: u32,
+    pub discard_observation: u32,
+}
+impl Default for RepairCosts {
+    fn default() -> Self {
+        RepairCosts {
+            remove_edge: 1,
+            flip_direction: 1,
+            flip_sign: 1,
+            add_edge: 2,
+            new_influence: 2,
+            discard_observation: 2,
+        }
+    }
+}
+impl fmt::Display for RepairCosts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "remove={},flip={},sign={},add={},influence={},observation={}",
+            self.remove_edge,
+            self.flip_direction,
+            self.flip_sign,
+            self.add_edge,
+            self.new_influence,
+            self.discard_observation
+        )
+    }
+}
+/// Parses costs like `remove=1,add=3`, operations that are not given keep their default cost
+impl FromStr for RepairCosts {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut costs = RepairCosts::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (operation, cost) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected OPERATION=COST, found '{entry}'"))?;
+            let cost: u32 = match cost.trim().parse() {
+                Ok(cost) if cost > 0 => cost,
+                _ => bail!("the cost of {operation} must be a positive integer, found '{cost}'"),
+            };
+            match operation.trim() {
+                "remove" => costs.remove_edge = cost,
+                "flip" => costs.flip_direction = cost,
+                "sign" => costs.flip_sign = cost,
+                "add" => costs.add_edge = cost,
+                "influence" => costs.new_influence = cost,
+                "observation" => costs.discard_observation = cost,
+                _ => bail!(
+                    "unknown repair operation '{operation}', expected remove, flip, sign, add, influence or observation"
+                ),
+            }
+        }
+        Ok(costs)
+    }
+}
+impl RepairCosts {
+    /// the arguments that set the costs in the encoding
+    fn clingo_arguments(&self) -> Vec<String> {
+        vec![
+            format!("--const=remove_edge_cost={}", self.remove_edge),
+            format!("--const=flip_direction_cost={}", self.flip_direction),
+            format!("--const=flip_sign_cost={}", self.flip_sign),
+            format!("--const=add_edge_cost={}", self.add_edge),
+            format!("--const=new_influence_cost={}", self.new_influence),
+            format!(
+                "--const=discard_observation_cost={}",
+                self.discard_observation
+            ),
+        ]
+    }
+
+    /// Returns the number and the cost of the operations of each type in the repair set
+    pub fn breakdown(&self, repairs: &[RepairOp]) -> CostBreakdown {
+        let mut breakdown = CostBreakdown::default();
+        for repair in repairs {
+            let (operation, cost) = match repair {
+                RepairOp::RemoveEdge(_) => (&mut breakdown.remove_edge, self.remove_edge),
+                RepairOp::FlipEdgeDirection(_) => {
+                    (&mut breakdown.flip_direction, self.flip_direction)
+                }
+                RepairOp::FlipEdgeSign(_) => (&mut breakdown.flip_sign, self.flip_sign),
+                RepairOp::AddEdge(_) => (&mut breakdown.add_edge, self.add_edge),
+                RepairOp::NewInfluence { .. } => (&mut breakdown.new_influence, self.new_influence),
+                RepairOp::FlipNodeSign { .. } => {
+                    (&mut breakdown.discard_observation, self.discard_observation)
+                }
+            };
+            operation.count += 1;
+            operation.cost += u64::from(cost);
+            breakdown.total += u64::from(cost);
+        }
+        breakdown
+    }
+}
+
+/// The number and the total cost of the operations of one type
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OperationCost {
+    pub count: u64,
+    pub cost: u64,
+}
+/// The cost of a repair set per type of operation
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CostBreakdown {
+    pub remove_edge: OperationCost,
+    pub flip_direction: OperationCost,
+    pub flip_sign: OperationCost,
+    pub add_edge: OperationCost,
+    pub new_influence: OperationCost,
+    pub discard_observation: OperationCost,
+    pub total: u64,
+}
+impl fmt::Display for CostBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operations = [
+            ("removed edges", &self.remove_edge),
+            ("flipped directions", &self.flip_direction),
+            ("flipped signs", &self.flip_sign),
+            ("added edges", &self.add_edge),
+            ("new influences", &self.new_influence),
+            ("discarded observations", &self.discard_observation),
+        ];
+        let parts: Vec<String> = operations
+            .iter()
+            .filter(|(_, operation)| operation.count > 0)
+            .map(|(name, operation)| format!("{} {name}: {}", operation.count, operation.cost))
+            .collect();
+        write!(f, "{} ({})", self.total, parts.join(", "))
+    }
+}
+
+/// The formats of network files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkFormat {
+    Cif,
+    SbmlQual,
+    Bnet,
+    Sif,
+}
+impl NetworkFormat {
+    /// Returns the format given by the extension of the file, CIF for unknown extensions
+    pub fn of(file: &Path) -> NetworkFormat {
+        match file.extension().and_then(|e| e.to_str()) {
+            Some("sbml" | "xml") => NetworkFormat::SbmlQual,
+            Some("bnet") => NetworkFormat::Bnet,
+            Some("sif") => NetworkFormat::Sif,
+            _ => NetworkFormat::Cif,
+        }
+    }
+}
+
+/// Reads a network in the format given by the file extension and resolves the aliases of its nodes.
+///
+/// Warnings about constructs that cannot be represented exactly are logged.
+pub fn read_network(
+    network_file: &Path,
+    aliases: &Option<Aliases>,
+    report: &mut AliasReport,
+) -> Result<Graph> {
+    let f =
+        File::open(network_file).context(format!("unable to open '{}'", network_file.display()))?;
+    let rename = |id: &str| match aliases {
+        Some(aliases) => aliases.map(id, report),
+        None => id.to_string(),
+    };
+    let graph = match NetworkFormat::of(network_file) {
+        NetworkFormat::SbmlQual => sbml_parser::read_renamed(&f, rename),
+        NetworkFormat::Bnet => bnet_parser::read_renamed(&f, rename),
+        NetworkFormat::Sif => sif_parser::read_renamed(&f, rename),
+        NetworkFormat::Cif => cif_parser::read_renamed(&f, rename).map(|graph| (graph, vec![])),
+    }
+    .context(format!("unable to parse '{}'", network_file.display()))?;
+    let (graph, warnings) = graph;
+    for warning in warnings {
+        warn!("{warning}");
+    }
+    Ok(graph)
+}
+
 pub fn compute_auto_inputs(graph: &FactBase, json: bool) -> Result<FactBase> {
     let new_inputs = guess_inputs(graph)?;
     let x = new_inputs
@@ -226,6 +715,14 @@ pub fn compute_auto_inputs(graph: &FactBase, json: bool) -> Result<FactBase> {
     Ok(new_inputs)
 }
 
+/// Returns the input nodes of input facts, e.g. computed by [`guess_inputs`]
+pub fn input_nodes(inputs: &FactBase) -> Result<Vec<NodeId>> {
+    inputs
+        .iter()
+        .map(|input| into_node_id(input.arguments()?[0]))
+        .collect()
+}
+
 pub fn check_observations(profile: &FactBase) -> Result<CheckResult> {
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec![])?;
@@ -234,21 +731,14 @@ pub fn check_observations(profile: &FactBase) -> Result<CheckResult> {
     ctl.add("base", &[], PRG_CONTRADICTORY_OBS)?;
     ctl.add_facts(profile)?;
 
-    // ground the base part
-    let part = Part::new("base", vec![])?;
-    let parts = vec![part];
-    ctl.ground(&parts)?;
-
-    // solve
-    let mut handle = ctl.solve(SolveMode::YIELD, &[])?;
+    // ground & solve
+    let mut handle = ground_and_solve(ctl, "check observations")?;
 
-    handle.resume()?;
-    match handle.model() {
-        Ok(Some(model)) => {
-            let atoms = model.symbols(ShowType::SHOWN)?;
+    match handle.next_any_model()? {
+        Some(atoms) => {
+            // close the solve handle
+            handle.close()?;
             if atoms.is_empty() {
-                // close the solve handle
-                handle.close()?;
                 return Ok(CheckResult::Consistent);
             }
 
@@ -299,6 +789,11 @@ pub fn check_observations(profile: &FactBase) -> Result<CheckResult> {
                             "Behavior +(increase) while initial level is set to Max in node {node} is contradictory."
                         ));
                     }
+                    "contradiction8" => {
+                        v.push(format!(
+                            "Simultaneous CHANGE and 0 behavior in node {node} is contradictory."
+                        ));
+                    }
                     _ => {
                         v.push("Unknown contradiction in observations".to_string());
                     }
@@ -319,20 +814,12 @@ pub fn guess_inputs(graph: &FactBase) -> Result<FactBase> {
     ctl.add("base", &[], PRG_GUESS_INPUTS)?;
     ctl.add_facts(graph)?;
 
-    // ground the base part
-    let part = Part::new("base", vec![])?;
-    let parts = vec![part];
-    ctl.ground(&parts)?;
-
-    // solve
-    let mut handle = ctl.solve(SolveMode::YIELD, &[])?;
-
-    handle.resume()?;
+    // ground & solve
+    let mut handle = ground_and_solve(ctl, "guess inputs")?;
 
     let mut inputs = FactBase::new();
 
-    if let Ok(Some(model)) = handle.model() {
-        let atoms = model.symbols(ShowType::SHOWN)?;
+    if let Some(atoms) = handle.next_any_model()? {
         if !atoms.is_empty() {
             for atom in atoms {
                 inputs.insert(&atom);
@@ -393,23 +880,100 @@ impl FunctionHandler for MemberFH {
         }
     }
 }
-fn ground_and_solve(ctl: Control) -> Result<SolveHandleWithFH<MemberFH>> {
-    // declare extern function handler
-    let member_fh = MemberFH;
+/// A running query that reports its progress and collects solver statistics
+struct QueryHandle {
+    query: &'static str,
+    handle: SolveHandleWithFH<MemberFH>,
+    ground_time: f64,
+    solve_start: Instant,
+    models: u64,
+    costs: Vec<Vec<i64>>,
+}
+impl QueryHandle {
+    /// search the next model and report it to the progress callback
+    fn next_model(&mut self) -> Result<Option<&Model>> {
+        self.handle.resume()?;
+        let model = self.handle.model()?;
+        if let Some(model) = model {
+            self.models += 1;
+            if progress_enabled() {
+                let cost = model.cost()?;
+                report_progress(Progress::Model {
+                    query: self.query.to_string(),
+                    number: self.models,
+                    cost: cost.clone(),
+                    optimal: model.optimality_proven()?,
+                });
+                if !cost.is_empty() {
+                    self.costs.push(cost);
+                }
+            }
+        }
+        Ok(model)
+    }
 
-    // ground the base part
-    let part = Part::new("base", vec![])?;
-    let parts = vec![part];
+    /// returns the symbols of the next model whose optimality is proven
+    fn next_optimal_model(&mut self) -> Result<Option<Vec<Symbol>>> {
+        loop {
+            match self.next_model()? {
+                Some(model) => {
+                    if model.optimality_proven()? {
+                        return Ok(Some(model.symbols(ShowType::SHOWN)?));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
 
-    let mut ctl = ctl.register_function_handler(member_fh);
-    ctl.ground(&parts)
-        .expect("ground with event handler did not work.");
+    /// returns the symbols of the next model
+    fn next_any_model(&mut self) -> Result<Option<Vec<Symbol>>> {
+        match self.next_model()? {
+            Some(model) => Ok(Some(model.symbols(ShowType::SHOWN)?)),
+            None => Ok(None),
+        }
+    }
 
-    // solve
-    let x = ctl.solve(SolveMode::YIELD, &[])?;
-    Ok(x)
+    /// stop the search and report the statistics of the query
+    fn close(self) -> Result<()> {
+        let solve_time = self.solve_start.elapsed().as_secs_f64();
+        let ctl = self.handle.close()?;
+        if progress_enabled() {
+            let stats = ctl.statistics()?;
+            report_progress(Progress::Finished(SolverStatistics {
+                query: self.query.to_string(),
+                atoms: statistics_value(stats, &["problem", "lp", "atoms"])? as u64,
+                rules: statistics_value(stats, &["problem", "lp", "rules"])? as u64,
+                choices: statistics_value(stats, &["solving", "solvers", "choices"])? as u64,
+                conflicts: statistics_value(stats, &["solving", "solvers", "conflicts"])? as u64,
+                models: self.models,
+                ground_time: self.ground_time,
+                solve_time,
+                costs: self.costs,
+            }));
+        }
+        Ok(())
+    }
 }
-fn ground(ctl: Control) -> Result<ControlWithFH> {
+
+/// returns the value of a statistics entry or 0 if the entry does not exist
+fn statistics_value(stats: &Statistics, path: &[&str]) -> Result<f64> {
+    let mut key = stats.root()?;
+    for name in path {
+        match stats.statistics_type(key)? {
+            StatisticsType::Map if stats.map_has_subkey(key, name)? => {
+                key = stats.map_at(key, name)?;
+            }
+            _ => return Ok(0.0),
+        }
+    }
+    match stats.statistics_type(key)? {
+        StatisticsType::Value => Ok(stats.value_get(key)?),
+        _ => Ok(0.0),
+    }
+}
+
+fn ground_and_solve(ctl: Control, query: &'static str) -> Result<QueryHandle> {
     // declare extern function handler
     let member_fh = MemberFH;
 
@@ -418,37 +982,46 @@ fn ground(ctl: Control) -> Result<ControlWithFH> {
     let parts = vec![part];
 
     let mut ctl = ctl.register_function_handler(member_fh);
-    ctl.ground(&parts)?;
-    Ok(ctl)
+    let ground_start = Instant::now();
+    ctl.ground(&parts)
+        .with_context(|| format!("unable to ground the query {query}"))?;
+    let ground_time = ground_start.elapsed().as_secs_f64();
+    report_progress(Progress::Grounded {
+        query: query.to_string(),
+        time: ground_time,
+    });
+
+    // solve
+    let handle = ctl.solve(SolveMode::YIELD, &[])?;
+    Ok(QueryHandle {
+        query,
+        handle,
+        ground_time,
+        solve_start: Instant::now(),
+        models: 0,
+        costs: vec![],
+    })
 }
 
-fn cautious_consequences_optimal_models(
-    handle: &mut SolveHandleWithFH<MemberFH>,
-) -> Result<Vec<Symbol>> {
+fn cautious_consequences_optimal_models(mut handle: QueryHandle) -> Result<Vec<Symbol>> {
     let mut symbols = vec![];
-    loop {
-        handle.resume()?;
-        match handle.model()? {
-            Some(model) => {
-                if model.optimality_proven()? {
-                    symbols = model.symbols(ShowType::SHOWN)?;
-                }
-            }
-            None => break,
-        }
+    while let Some(model) = handle.next_optimal_model()? {
+        symbols = model;
     }
+    handle.close()?;
     Ok(symbols)
 }
 
-fn get_optimum<FH: FunctionHandler>(handle: &mut SolveHandleWithFH<FH>) -> Result<Vec<i64>> {
+fn get_optimum(mut handle: QueryHandle) -> Result<Vec<i64>> {
     let mut last = vec![];
     let mut found = false;
     loop {
-        handle.resume()?;
-        match handle.model()? {
+        match handle.next_model()? {
             Some(model) => {
                 if model.optimality_proven()? {
-                    return Ok(model.cost()?);
+                    let cost = model.cost()?;
+                    handle.close()?;
+                    return Ok(cost);
                 } else {
                     found = true;
                     last = model.cost()?;
@@ -456,6 +1029,7 @@ fn get_optimum<FH: FunctionHandler>(handle: &mut SolveHandleWithFH<FH>) -> Resul
             }
             None => {
                 if found {
+                    handle.close()?;
                     return Ok(last);
                 } else {
                     panic!("Error: no optimal model found!");
@@ -465,7 +1039,175 @@ fn get_optimum<FH: FunctionHandler>(handle: &mut SolveHandleWithFH<FH>) -> Resul
     }
 }
 
+/// collects the optimal models of a query
+fn optimal_models<T>(
+    mut handle: QueryHandle,
+    extract: fn(&[Symbol]) -> Result<T>,
+) -> Result<Vec<T>> {
+    let mut models = vec![];
+    while let Some(symbols) = handle.next_optimal_model()? {
+        models.push(extract(&symbols)?);
+    }
+    handle.close()?;
+    Ok(models)
+}
+
+/// The result of a consistency check of a subset of the nodes
+enum Check {
+    Consistent,
+    /// inconsistent, with the indices of an inconsistent subset
+    Inconsistent(Vec<usize>),
+    Timeout,
+}
+
+/// Checks the consistency of subnetworks.
+///
+/// A node is active if the consistency constraints of the setting apply to it,
+/// an inactive node is unconstrained like an input.
+/// The active nodes are externals, so the program is grounded only once
+/// and each check solves under the assumption of the selected nodes.
+struct ConsistencyChecker {
+    ctl: Option<ControlWithFH<MemberFH>>,
+    /// the solver literals of the active nodes
+    literals: Vec<SolverLiteral>,
+    deadline: Option<Instant>,
+}
+impl ConsistencyChecker {
+    fn new(
+        graph: &FactBase,
+        profile: &FactBase,
+        inputs: &FactBase,
+        nodes: &[Symbol],
+        setting: &Setting,
+    ) -> Result<ConsistencyChecker> {
+        let mut ctl = clingo::control(vec![])?;
+        ctl.add_facts(graph)?;
+        ctl.add_facts(profile)?;
+        ctl.add_facts(inputs)?;
+        let mut externals = String::new();
+        for node in nodes {
+            externals.push_str(&format!("#external active({node}).\n"));
+        }
+        externals.push_str("input(E,V) :- exp(E), vertex(V), not active(V).\n");
+        ctl.add("base", &[], &externals)?;
+        ctl.add("base", &[], PRG_SIGN_CONS)?;
+        ctl.add("base", &[], PRG_BWD_PROP)?;
+
+        if setting.os {
+            ctl.add("base", &[], PRG_ONE_STATE)?;
+        }
+        if setting.fp {
+            ctl.add("base", &[], PRG_FWD_PROP)?;
+        }
+        if setting.fc {
+            ctl.add("base", &[], PRG_FOUNDEDNESS)?;
+        }
+        if setting.ep {
+            ctl.add("base", &[], PRG_ELEM_PATH)?;
+        }
+        ctl.add("base", &[], PRG_KEEP_OBSERVATIONS)?;
+
+        add_user_programs(&mut ctl, setting)?;
+
+        let mut ctl = ctl.register_function_handler(MemberFH);
+        let ground_start = Instant::now();
+        ctl.ground(&[Part::new("base", vec![])?])?;
+        report_progress(Progress::Grounded {
+            query: "mics".to_string(),
+            time: ground_start.elapsed().as_secs_f64(),
+        });
+
+        let mut literals = HashMap::new();
+        for atom in ctl
+            .symbolic_atoms()?
+            .iter_with_signature(Signature::new("active", 1, true)?)?
+        {
+            literals.insert(atom.symbol()?.arguments()?[0], atom.literal()?);
+        }
+        let literals = nodes
+            .iter()
+            .map(|node| {
+                literals
+                    .get(node)
+                    .copied()
+                    .ok_or_else(|| anyhow!("node {node} is not grounded"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(ConsistencyChecker {
+            ctl: Some(ctl),
+            literals,
+            deadline: None,
+        })
+    }
+
+    /// checks the consistency of the subnetwork with the given active nodes
+    fn check(&mut self, subset: &[usize]) -> Result<Check> {
+        let ctl = match self.ctl.take() {
+            Some(ctl) => ctl,
+            None => return Ok(Check::Timeout),
+        };
+        let assumptions: Vec<SolverLiteral> = subset.iter().map(|i| self.literals[*i]).collect();
+        let mut handle = ctl.solve(SolveMode::ASYNC | SolveMode::YIELD, &assumptions)?;
+        if let Some(deadline) = self.deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !handle.wait(remaining) {
+                handle.cancel()?;
+                handle.close()?;
+                return Ok(Check::Timeout);
+            }
+        }
+        let result = handle.get()?;
+        let check = if result.contains(SolveResult::SATISFIABLE) {
+            Check::Consistent
+        } else {
+            let core = handle.core()?;
+            Check::Inconsistent(
+                subset
+                    .iter()
+                    .copied()
+                    .filter(|i| core.contains(&self.literals[*i]))
+                    .collect(),
+            )
+        };
+        self.ctl = Some(handle.close()?);
+        Ok(check)
+    }
+}
+
+/// Returns the next subset of the nodes that contains no known mic
+/// and is no subset of a known consistent subnetwork, preferring large subsets
+fn next_seed(map: Control) -> Result<(Control, Option<Vec<usize>>)> {
+    let mut handle = map.solve(SolveMode::YIELD, &[])?;
+    handle.resume()?;
+    let seed = match handle.model()? {
+        Some(model) => {
+            let mut seed = vec![];
+            for symbol in model.symbols(ShowType::SHOWN)? {
+                seed.push(symbol.arguments()?[0].number()? as usize);
+            }
+            seed.sort_unstable();
+            Some(seed)
+        }
+        None => None,
+    };
+    Ok((handle.close()?, seed))
+}
+
+/// Adds a constraint to the map of explored subsets
+fn block(map: &mut Control, name: &str, constraint: &str) -> Result<()> {
+    map.add(name, &[], constraint)?;
+    map.ground(&[Part::new(name, vec![])?])?;
+    Ok(())
+}
+
 /// return the minimal inconsistent cores
+///
+/// A minimal inconsistent core (mic) is a minimal set of nodes whose consistency constraints
+/// cannot be satisfied together with the observations,
+/// it is inconsistent under the consistency notion of the setting
+/// and becomes consistent when any of its nodes is left unconstrained.
+/// Inputs are never part of a mic.
+/// The profile may contain several experiments.
 pub fn get_minimal_inconsistent_cores(
     graph: &FactBase,
     profile: &FactBase,
@@ -473,39 +1215,150 @@ pub fn get_minimal_inconsistent_cores(
     setting: &Setting,
 ) -> Result<Mics> {
     info!("Computing minimal inconsistent cores (mic\'s) ...");
-    // create a control object and pass command line arguments
-    let mut ctl: Control = clingo::control(vec![
-        "0".to_string(),
-        "--dom-mod=5,16".to_string(),
-        "--heu=Domain".to_string(),
-        "--enum-mode=domRec".to_string(),
-    ])?;
-
-    ctl.add_facts(graph)?;
-    ctl.add_facts(profile)?;
-    ctl.add_facts(inputs)?;
-    ctl.add("base", &[], PRG_MICS)?;
-
-    if setting.fp {
-        ctl.add("base", &[], PRG_FWD_PROP)?;
+    let mut experiments = 0;
+    let mut input_experiments: HashMap<Symbol, usize> = HashMap::new();
+    for symbol in profile.iter() {
+        match (symbol.name()?, symbol.arguments()?.as_slice()) {
+            ("exp", [_]) => experiments += 1,
+            ("input", [_, node]) => *input_experiments.entry(*node).or_default() += 1,
+            _ => {}
+        }
+    }
+    for symbol in inputs.iter() {
+        if let ("input", [node]) = (symbol.name()?, symbol.arguments()?.as_slice()) {
+            input_experiments.insert(*node, usize::MAX);
+        }
     }
+    // nodes that are inputs in all experiments are never constrained
+    let mut nodes = vec![];
+    for symbol in graph.iter() {
+        if let ("vertex", [node]) = (symbol.name()?, symbol.arguments()?.as_slice()) {
+            if input_experiments.get(node).copied().unwrap_or_default() < experiments.max(1) {
+                nodes.push(*node);
+            }
+        }
+    }
+    let checker = ConsistencyChecker::new(graph, profile, inputs, &nodes, setting)?;
+
+    // the map of explored subsets, on(I) selects the node I
+    let mut map: Control = clingo::control(vec!["--heu=Domain".to_string()])?;
+    let mut program = String::new();
+    for i in 0..nodes.len() {
+        program.push_str(&format!("{{ on({i}) }}.\n#heuristic on({i}). [1,true]\n"));
+    }
+    program.push_str("#show on/1.\n");
+    map.add("base", &[], &program)?;
+    map.ground(&[Part::new("base", vec![])?])?;
+
+    Ok(Mics {
+        checker,
+        map: Some(map),
+        nodes,
+        blocks: 0,
+        complete: false,
+    })
+}
 
-    // ground & solve
-    let ctl = ground(ctl)?;
-    Ok(Mics(ctl.all_models()?))
+/// Iterator over the minimal inconsistent cores, each given by its nodes.
+///
+/// The cores are enumerated by exploring the subsets of the nodes (MARCO),
+/// so every core is found exactly once.
+pub struct Mics {
+    checker: ConsistencyChecker,
+    /// the map of explored subsets, `None` when the search has stopped
+    map: Option<Control>,
+    nodes: Vec<Symbol>,
+    blocks: usize,
+    complete: bool,
+}
+impl Mics {
+    /// Stops the search when the timeout is reached
+    pub fn with_timeout(mut self, timeout: Duration) -> Mics {
+        self.checker.deadline = Some(Instant::now() + timeout);
+        self
+    }
+    /// Returns `true` if all minimal inconsistent cores have been found
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+    fn next_core(&mut self) -> Result<Option<Vec<Symbol>>> {
+        while let Some(map) = self.map.take() {
+            let (map, seed) = next_seed(map)?;
+            let mut seed = match seed {
+                Some(seed) => seed,
+                None => {
+                    self.complete = true;
+                    return Ok(None);
+                }
+            };
+            self.blocks += 1;
+            let name = format!("block{}", self.blocks);
+            match self.checker.check(&seed)? {
+                Check::Timeout => return Ok(None),
+                Check::Inconsistent(mut core) => {
+                    // shrink the core until leaving any node unconstrained makes it consistent
+                    let mut i = 0;
+                    while i < core.len() {
+                        let mut smaller = core.clone();
+                        smaller.remove(i);
+                        match self.checker.check(&smaller)? {
+                            Check::Timeout => return Ok(None),
+                            Check::Inconsistent(smaller) => core = smaller,
+                            Check::Consistent => i += 1,
+                        }
+                    }
+                    if core.is_empty() {
+                        // the observations are inconsistent without any constrained node
+                        self.complete = true;
+                        return Ok(Some(vec![]));
+                    }
+                    let mut map = map;
+                    let constraint: Vec<String> = core.iter().map(|i| format!("on({i})")).collect();
+                    block(&mut map, &name, &format!(":- {}.", constraint.join(", ")))?;
+                    self.map = Some(map);
+                    return Ok(Some(core.iter().map(|i| self.nodes[*i]).collect()));
+                }
+                Check::Consistent => {
+                    // grow the seed until constraining any other node makes it inconsistent
+                    for i in 0..self.nodes.len() {
+                        if seed.contains(&i) {
+                            continue;
+                        }
+                        let mut larger = seed.clone();
+                        larger.push(i);
+                        match self.checker.check(&larger)? {
+                            Check::Timeout => return Ok(None),
+                            Check::Inconsistent(_) => {}
+                            Check::Consistent => seed = larger,
+                        }
+                    }
+                    let constraint: Vec<String> = (0..self.nodes.len())
+                        .filter(|i| !seed.contains(i))
+                        .map(|i| format!("not on({i})"))
+                        .collect();
+                    if constraint.is_empty() {
+                        // the whole network is consistent
+                        self.complete = true;
+                        return Ok(None);
+                    }
+                    let mut map = map;
+                    block(&mut map, &name, &format!(":- {}.", constraint.join(", ")))?;
+                    self.map = Some(map);
+                }
+            }
+        }
+        Ok(None)
+    }
 }
-pub struct Mics(AllModels<Non, Non, Non, MemberFH, Non>);
 impl Iterator for Mics {
     type Item = Vec<Symbol>;
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0.next() {
-            None => None,
-            Some(model) => {
-                let extract = extract_mics(&model.symbols);
-                match extract {
-                    Ok(x) => Some(x),
-                    _ => None,
-                }
+        match self.next_core() {
+            Ok(core) => core,
+            Err(e) => {
+                warn!("Computing minimal inconsistent cores failed: {e}");
+                self.map = None;
+                None
             }
         }
     }
@@ -547,9 +1400,11 @@ pub fn get_scenfit(
     ctl.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
     ctl.add("base", &[], PRG_KEEP_INPUTS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    Ok(get_optimum(&mut handle)?[0])
+    let handle = ground_and_solve(ctl, "scenfit")?;
+    Ok(get_optimum(handle)?[0])
 }
 
 /// returns a vector of scenfit labelings of data and model
@@ -564,6 +1419,33 @@ pub fn get_scenfit_labelings(
     number: u32,
     setting: &Setting,
 ) -> Result<LabelsRepair> {
+    let handle = scenfit_labelings_query(graph, profile, inputs, number, setting)?;
+    Ok(LabelsRepair(Some(handle)))
+}
+
+/// returns a vector of scenfit labelings of data and model with the labels of each experiment
+///
+/// # Arguments:
+///
+/// + number - maximal number of labelings
+pub fn get_scenfit_labelings_per_experiment(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    number: u32,
+    setting: &Setting,
+) -> Result<ExperimentLabelsRepair> {
+    let handle = scenfit_labelings_query(graph, profile, inputs, number, setting)?;
+    Ok(ExperimentLabelsRepair(Some(handle)))
+}
+
+fn scenfit_labelings_query(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    number: u32,
+    setting: &Setting,
+) -> Result<QueryHandle> {
     info!("Compute scenfit labelings ...");
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec![
@@ -599,22 +1481,41 @@ pub fn get_scenfit_labelings(
     ctl.add("base", &[], PRG_SHOW_ERRORS)?;
     ctl.add("base", &[], PRG_SHOW_LABELS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let ctl = ground(ctl)?;
-    Ok(LabelsRepair(ctl.optimal_models()?))
+    ground_and_solve(ctl, "scenfit labelings")
 }
-pub struct LabelsRepair(OptimalModels<Non, Non, Non, MemberFH, Non>);
+pub struct LabelsRepair(Option<QueryHandle>);
 impl Iterator for LabelsRepair {
     type Item = (Vec<Prediction>, Vec<RepairOp>);
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0.next() {
-            None => None,
-            Some(model) => {
-                let extract = extract_labels_repairs(&model.symbols);
-                match extract {
-                    Ok(x) => Some(x),
-                    _ => None,
+        let handle = self.0.as_mut()?;
+        match handle.next_optimal_model() {
+            Ok(Some(symbols)) => extract_labels_repairs(&symbols).ok(),
+            _ => {
+                if let Some(handle) = self.0.take() {
+                    handle.close().ok();
                 }
+                None
+            }
+        }
+    }
+}
+/// labels of the nodes in each experiment
+pub type ExperimentLabels = Vec<(ProfileId, Vec<Prediction>)>;
+pub struct ExperimentLabelsRepair(Option<QueryHandle>);
+impl Iterator for ExperimentLabelsRepair {
+    type Item = (ExperimentLabels, Vec<RepairOp>);
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.0.as_mut()?;
+        match handle.next_optimal_model() {
+            Ok(Some(symbols)) => extract_experiment_labels_repairs(&symbols).ok(),
+            _ => {
+                if let Some(handle) = self.0.take() {
+                    handle.close().ok();
+                }
+                None
             }
         }
     }
@@ -656,9 +1557,11 @@ pub fn get_mcos(
     ctl.add("base", &[], PRG_MIN_ADDED_INFLUENCES)?;
     ctl.add("base", &[], PRG_KEEP_OBSERVATIONS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    Ok(get_optimum(&mut handle)?[0])
+    let handle = ground_and_solve(ctl, "mcos")?;
+    Ok(get_optimum(handle)?[0])
 }
 
 /// returns a vector of mcos labelings of data and model
@@ -673,6 +1576,33 @@ pub fn get_mcos_labelings(
     number: u32,
     setting: &Setting,
 ) -> Result<LabelsRepair> {
+    let handle = mcos_labelings_query(graph, profile, inputs, number, setting)?;
+    Ok(LabelsRepair(Some(handle)))
+}
+
+/// returns a vector of mcos labelings of data and model with the labels of each experiment
+///
+/// # Arguments:
+///
+/// + number - maximal number of labelings
+pub fn get_mcos_labelings_per_experiment(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    number: u32,
+    setting: &Setting,
+) -> Result<ExperimentLabelsRepair> {
+    let handle = mcos_labelings_query(graph, profile, inputs, number, setting)?;
+    Ok(ExperimentLabelsRepair(Some(handle)))
+}
+
+fn mcos_labelings_query(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    number: u32,
+    setting: &Setting,
+) -> Result<QueryHandle> {
     info!("Compute mcos labelings ...");
 
     // create a control object and pass command line arguments
@@ -709,9 +1639,10 @@ pub fn get_mcos_labelings(
     ctl.add("base", &[], PRG_SHOW_REPAIRS)?;
     ctl.add("base", &[], PRG_SHOW_LABELS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let ctl = ground(ctl)?;
-    Ok(LabelsRepair(ctl.optimal_models()?))
+    ground_and_solve(ctl, "mcos labelings")
 }
 pub fn get_predictions_under_mcos(
     graph: &FactBase,
@@ -719,6 +1650,27 @@ pub fn get_predictions_under_mcos(
     inputs: &FactBase,
     setting: &Setting,
 ) -> Result<Predictions> {
+    let model = predictions_under_mcos_model(graph, profile, inputs, setting)?;
+    extract_predictions(&model)
+}
+
+/// returns the predictions under mcos for each experiment
+pub fn get_predictions_per_experiment_under_mcos(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    setting: &Setting,
+) -> Result<Vec<(ProfileId, Predictions)>> {
+    let model = predictions_under_mcos_model(graph, profile, inputs, setting)?;
+    extract_experiment_predictions(&model)
+}
+
+fn predictions_under_mcos_model(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    setting: &Setting,
+) -> Result<Vec<Symbol>> {
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec![
         "--opt-strategy=5".to_string(),
@@ -756,10 +1708,11 @@ pub fn get_predictions_under_mcos(
         ctl.add("base", &[], PRG_PREDICTIONS_DM)?;
     }
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    let model = cautious_consequences_optimal_models(&mut handle)?;
-    extract_predictions(&model)
+    let handle = ground_and_solve(ctl, "predictions under mcos")?;
+    cautious_consequences_optimal_models(handle)
 }
 
 pub fn get_predictions_under_scenfit(
@@ -768,6 +1721,27 @@ pub fn get_predictions_under_scenfit(
     inputs: &FactBase,
     setting: &Setting,
 ) -> Result<Predictions> {
+    let model = predictions_under_scenfit_model(graph, profile, inputs, setting)?;
+    extract_predictions(&model)
+}
+
+/// returns the predictions under scenfit for each experiment
+pub fn get_predictions_per_experiment_under_scenfit(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    setting: &Setting,
+) -> Result<Vec<(ProfileId, Predictions)>> {
+    let model = predictions_under_scenfit_model(graph, profile, inputs, setting)?;
+    extract_experiment_predictions(&model)
+}
+
+fn predictions_under_scenfit_model(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    setting: &Setting,
+) -> Result<Vec<Symbol>> {
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec![
         "--opt-strategy=5".to_string(),
@@ -805,10 +1779,11 @@ pub fn get_predictions_under_scenfit(
         ctl.add("base", &[], PRG_PREDICTIONS_DM)?;
     }
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    let model = cautious_consequences_optimal_models(&mut handle)?;
-    extract_predictions(&model)
+    let handle = ground_and_solve(ctl, "predictions under scenfit")?;
+    cautious_consequences_optimal_models(handle)
 }
 
 fn extract_addeddy(symbols: &[Symbol]) -> Result<Symbol> {
@@ -871,6 +1846,7 @@ pub enum Direction {
     ZeroToMinus,
     NotMinusToMinus,
     NotPlusToPlus,
+    ChangeToZero,
 }
 pub fn into_repair(symbol: &Symbol) -> Result<RepairOp> {
     match symbol.name()? {
@@ -884,13 +1860,29 @@ pub fn into_repair(symbol: &Symbol) -> Result<RepairOp> {
                 _ => panic!("unexpected EdgeSign"),
             };
 
-            Ok(RepairOp::AddEdge(ObsELabel {
+            Ok(RepairOp::AddEdge(ObsELabel {
+                start,
+                target,
+                sign,
+            }))
+        }
+        "remedge" => {
+            let arguments = symbol.arguments()?;
+            let start = into_node_id(arguments[0])?;
+            let target = into_node_id(arguments[1])?;
+            let sign = match arguments[2].number() {
+                Ok(1) => EdgeSign::Plus,
+                Ok(-1) => EdgeSign::Minus,
+                _ => panic!("unexpected EdgeSign"),
+            };
+
+            Ok(RepairOp::RemoveEdge(ObsELabel {
                 start,
                 target,
                 sign,
             }))
         }
-        "remedge" => {
+        "flip" => {
             let arguments = symbol.arguments()?;
             let start = into_node_id(arguments[0])?;
             let target = into_node_id(arguments[1])?;
@@ -900,13 +1892,13 @@ pub fn into_repair(symbol: &Symbol) -> Result<RepairOp> {
                 _ => panic!("unexpected EdgeSign"),
             };
 
-            Ok(RepairOp::RemoveEdge(ObsELabel {
+            Ok(RepairOp::FlipEdgeDirection(ObsELabel {
                 start,
                 target,
                 sign,
             }))
         }
-        "flip" => {
+        "flip_edge_sign" => {
             let arguments = symbol.arguments()?;
             let start = into_node_id(arguments[0])?;
             let target = into_node_id(arguments[1])?;
@@ -916,7 +1908,7 @@ pub fn into_repair(symbol: &Symbol) -> Result<RepairOp> {
                 _ => panic!("unexpected EdgeSign"),
             };
 
-            Ok(RepairOp::FlipEdgeDirection(ObsELabel {
+            Ok(RepairOp::FlipEdgeSign(ObsELabel {
                 start,
                 target,
                 sign,
@@ -1018,6 +2010,18 @@ pub fn into_repair(symbol: &Symbol) -> Result<RepairOp> {
                 direction,
             })
         }
+        "flip_node_sign_change_to_0" => {
+            let arguments = symbol.arguments()?;
+            let profile = arguments[0].string()?.to_string();
+            let node = into_node_id(arguments[1])?;
+            let direction = Direction::ChangeToZero;
+
+            Ok(RepairOp::FlipNodeSign {
+                profile,
+                node,
+                direction,
+            })
+        }
         "new_influence" => {
             let arguments = symbol.arguments()?;
             let profile = arguments[0].string()?.to_string();
@@ -1041,10 +2045,15 @@ pub fn into_repair(symbol: &Symbol) -> Result<RepairOp> {
 }
 
 /// only apply with elementary path consistency notion
+///
+/// Adds one edge at a time, the result is not guaranteed to be optimal.
+/// `get_opt_add_remove_edges` computes the optimum, but grounding the paths over all edges
+/// that can be added is expensive for large networks.
 pub fn get_opt_add_remove_edges_greedy(
     graph: &FactBase,
     profiles: &FactBase,
     inputs: &FactBase,
+    setting: &Setting,
 ) -> Result<(i64, i64, std::vec::Vec<FactBase>)> {
     let mut ctl = clingo::control(vec![
         "--opt-strategy=5".to_string(),
@@ -1068,9 +2077,11 @@ pub fn get_opt_add_remove_edges_greedy(
     ctl.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
     ctl.add("base", &[], PRG_KEEP_INPUTS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    let optima = get_optimum(&mut handle)?;
+    let handle = ground_and_solve(ctl, "opt add remove edges greedy")?;
+    let optima = get_optimum(handle)?;
     let mut bscenfit = optima[0];
     let mut brepscore = optima[1];
 
@@ -1112,102 +2123,91 @@ pub fn get_opt_add_remove_edges_greedy(
         ctl.add("base", &[], PRG_ERROR_MEASURE)?;
         ctl.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
         ctl.add("base", &[], PRG_KEEP_INPUTS)?;
+        add_user_programs(&mut ctl, setting)?;
 
         // ground & solve
-        let mut handle = ground_and_solve(ctl)?;
+        let mut handle = ground_and_solve(ctl, "best edge end")?;
         // seach best edge end loop
-        loop {
-            handle.resume()?;
-            match handle.model()? {
-                Some(model) => {
-                    if model.optimality_proven()? {
-                        let symbols = model.symbols(ShowType::SHOWN)?;
-                        let cost = model.cost()?;
-
-                        let nscenfit = cost[0];
-                        let nrepscore = cost[1];
-
-                        if nscenfit < oscenfit || nrepscore < orepscore {
-                            // better score or more that 1 scenfit
-                            let nend = extract_addeddy(&symbols)?;
-
-                            let mut f_end = FactBase::new();
-                            f_end.insert(&nend);
-
-                            let mut ctl2 = clingo::control(vec![
-                                "--opt-strategy=5".to_string(),
-                                "--opt-mode=optN".to_string(),
-                                "--project".to_string(),
-                            ])?;
-                            ctl2.add_facts(graph)?;
-                            ctl2.add_facts(profiles)?;
-                            ctl2.add_facts(inputs)?;
-                            ctl2.add_facts(&oedges)?;
-                            ctl2.add_facts(&f_end)?;
-
-                            ctl2.add("base", &[], PRG_SIGN_CONS)?;
-                            ctl2.add("base", &[], PRG_BWD_PROP)?;
-                            ctl2.add("base", &[], PRG_FWD_PROP)?;
-                            ctl2.add("base", &[], PRG_ELEM_PATH)?;
-                            ctl2.add("base", &[], PRG_REMOVE_EDGES)?;
-                            ctl2.add("base", &[], PRG_BEST_EDGE_START)?;
-                            ctl2.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
-                            ctl2.add("base", &[], PRG_SHOW_REPAIRS)?;
-                            ctl2.add("base", &[], PRG_ERROR_MEASURE)?;
-                            ctl2.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
-                            ctl2.add("base", &[], PRG_KEEP_INPUTS)?;
-
-                            // ground & solve
-                            let mut handle2 = ground_and_solve(ctl2)?;
-                            // seach best edge start loop
-                            loop {
-                                handle2.resume()?;
-                                match handle2.model()? {
-                                    Some(model) => {
-                                        if model.optimality_proven()? {
-                                            let symbols2 = model.symbols(ShowType::SHOWN)?;
-                                            let n2scenfit = model.cost()?[0];
-                                            let n2repscore = model.cost()?[1];
-
-                                            if n2scenfit < oscenfit || n2repscore < orepscore {
-                                                // better score or more that 1 scenfit
-                                                if n2scenfit < bscenfit {
-                                                    bscenfit = n2scenfit; // update bscenfit
-                                                    brepscore = n2repscore;
-                                                }
-                                                if n2scenfit == bscenfit && n2repscore < brepscore {
-                                                    brepscore = n2repscore;
-                                                }
-
-                                                let nedges = extract_addedges(&symbols2)?;
-
-                                                let tuple = (nedges.clone(), n2scenfit, n2repscore);
-                                                if !fedges.contains(&tuple) {
-                                                    fedges.push(tuple);
-                                                }
-                                                end = false;
-                                            }
-                                        }
-                                    }
-                                    None => break,
+        while let Some(model) = handle.next_model()? {
+            if model.optimality_proven()? {
+                let symbols = model.symbols(ShowType::SHOWN)?;
+                let cost = model.cost()?;
+
+                let nscenfit = cost[0];
+                let nrepscore = cost[1];
+
+                if nscenfit < oscenfit || nrepscore < orepscore {
+                    // better score or more that 1 scenfit
+                    let nend = extract_addeddy(&symbols)?;
+
+                    let mut f_end = FactBase::new();
+                    f_end.insert(&nend);
+
+                    let mut ctl2 = clingo::control(vec![
+                        "--opt-strategy=5".to_string(),
+                        "--opt-mode=optN".to_string(),
+                        "--project".to_string(),
+                    ])?;
+                    ctl2.add_facts(graph)?;
+                    ctl2.add_facts(profiles)?;
+                    ctl2.add_facts(inputs)?;
+                    ctl2.add_facts(&oedges)?;
+                    ctl2.add_facts(&f_end)?;
+
+                    ctl2.add("base", &[], PRG_SIGN_CONS)?;
+                    ctl2.add("base", &[], PRG_BWD_PROP)?;
+                    ctl2.add("base", &[], PRG_FWD_PROP)?;
+                    ctl2.add("base", &[], PRG_ELEM_PATH)?;
+                    ctl2.add("base", &[], PRG_REMOVE_EDGES)?;
+                    ctl2.add("base", &[], PRG_BEST_EDGE_START)?;
+                    ctl2.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
+                    ctl2.add("base", &[], PRG_SHOW_REPAIRS)?;
+                    ctl2.add("base", &[], PRG_ERROR_MEASURE)?;
+                    ctl2.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
+                    ctl2.add("base", &[], PRG_KEEP_INPUTS)?;
+                    add_user_programs(&mut ctl2, setting)?;
+
+                    // ground & solve
+                    let mut handle2 = ground_and_solve(ctl2, "best edge start")?;
+                    // seach best edge start loop
+                    while let Some(model) = handle2.next_model()? {
+                        if model.optimality_proven()? {
+                            let symbols2 = model.symbols(ShowType::SHOWN)?;
+                            let n2scenfit = model.cost()?[0];
+                            let n2repscore = model.cost()?[1];
+
+                            if n2scenfit < oscenfit || n2repscore < orepscore {
+                                // better score or more that 1 scenfit
+                                if n2scenfit < bscenfit {
+                                    bscenfit = n2scenfit; // update bscenfit
+                                    brepscore = n2repscore;
                                 }
+                                if n2scenfit == bscenfit && n2repscore < brepscore {
+                                    brepscore = n2repscore;
+                                }
+
+                                let nedges = extract_addedges(&symbols2)?;
+
+                                let tuple = (nedges.clone(), n2scenfit, n2repscore);
+                                if !fedges.contains(&tuple) {
+                                    fedges.push(tuple);
+                                }
+                                end = false;
                             }
                         }
-                        if end {
-                            // could not get better
-                            let tuple = (oedges.clone(), oscenfit, orepscore);
-                            if !tedges.contains(&tuple)
-                                && oscenfit == bscenfit
-                                && orepscore == brepscore
-                            {
-                                tedges.push(tuple);
-                            }
-                        }
+                    }
+                    handle2.close()?;
+                }
+                if end {
+                    // could not get better
+                    let tuple = (oedges.clone(), oscenfit, orepscore);
+                    if !tedges.contains(&tuple) && oscenfit == bscenfit && orepscore == brepscore {
+                        tedges.push(tuple);
                     }
                 }
-                None => break,
             }
         }
+        handle.close()?;
     }
 
     // take only the results with the best scenfit
@@ -1220,28 +2220,34 @@ pub fn get_opt_add_remove_edges_greedy(
     Ok((bscenfit, brepscore, redges))
 }
 
+/// Edges added by the greedy search together with the scenfit and repair score they reach
+pub struct GreedyEdges<'a> {
+    pub edges: &'a FactBase,
+    pub scenfit: i64,
+    pub repair_score: i64,
+}
+
 /// only apply with elementary path consistency notion
 pub fn get_opt_repairs_add_remove_edges_greedy(
     graph: &FactBase,
     profiles: &FactBase,
     inputs: &FactBase,
-    edges: &FactBase,
-    scenfit: i64,
-    repair_score: i64,
+    greedy: &GreedyEdges,
     max_solutions: u32,
+    setting: &Setting,
 ) -> Result<Vec<std::vec::Vec<clingo::Symbol>>> {
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec![
         max_solutions.to_string(),
         "--opt-strategy=5".to_string(),
-        format!("--opt-mode=optN,{scenfit},{repair_score}"),
+        format!("--opt-mode=optN,{},{}", greedy.scenfit, greedy.repair_score),
         "--project".to_string(),
     ])?;
 
     ctl.add_facts(graph)?;
     ctl.add_facts(profiles)?;
     ctl.add_facts(inputs)?;
-    ctl.add_facts(edges)?;
+    ctl.add_facts(greedy.edges)?;
 
     ctl.add("base", &[], PRG_SIGN_CONS)?;
     ctl.add("base", &[], PRG_BWD_PROP)?;
@@ -1255,21 +2261,60 @@ pub fn get_opt_repairs_add_remove_edges_greedy(
     ctl.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
     ctl.add("base", &[], PRG_KEEP_INPUTS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let ctl = ground(ctl)?;
+    let handle = ground_and_solve(ctl, "opt repairs add remove edges greedy")?;
+    optimal_models(handle, extract_repairs)
+}
 
-    let models = ctl.optimal_models()?;
-    models
-        .map(|model| extract_repairs(&model.symbols))
-        .collect()
+/// Largest network (number of OR nodes) for which edges are added under the elementary path
+/// consistency notion in a single optimization.
+///
+/// Every pair of nodes can get a new edge, so the grounding contains the elementary paths of the
+/// complete graph, up to (n-1)! from each input for n nodes.
+pub const MAX_EXACT_ELEM_PATH_NODES: usize = 10;
+
+/// Returns true if the network is small enough to add edges under the elementary path
+/// consistency notion in a single optimization, see [`MAX_EXACT_ELEM_PATH_NODES`]
+pub fn supports_exact_elem_path(graph: &FactBase) -> Result<bool> {
+    Ok(or_vertex_count(graph)? <= MAX_EXACT_ELEM_PATH_NODES)
+}
+
+fn or_vertex_count(graph: &FactBase) -> Result<usize> {
+    let mut nodes = 0;
+    for fact in graph.iter() {
+        if fact.name()? == "vertex" && fact.arguments()?[0].name()? == "or" {
+            nodes += 1;
+        }
+    }
+    Ok(nodes)
+}
+
+fn check_exact_elem_path_size(graph: &FactBase, setting: &Setting) -> Result<()> {
+    if !setting.ep {
+        return Ok(());
+    }
+    let nodes = or_vertex_count(graph)?;
+    if nodes > MAX_EXACT_ELEM_PATH_NODES {
+        bail!(
+            "adding edges with elementary paths in a single optimization is limited to networks \
+             with at most {MAX_EXACT_ELEM_PATH_NODES} nodes, the network has {nodes} nodes"
+        );
+    }
+    Ok(())
 }
 
+/// Returns the optimal scenfit and repair score of adding and removing edges,
+/// exact also with the elementary path consistency notion for networks with at most
+/// [`MAX_EXACT_ELEM_PATH_NODES`] nodes
 pub fn get_opt_add_remove_edges(
     graph: &FactBase,
     profiles: &FactBase,
     inputs: &FactBase,
     setting: &Setting,
 ) -> Result<(i64, i64)> {
+    check_exact_elem_path_size(graph, setting)?;
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec!["--opt-strategy=5".to_string()])?;
 
@@ -1294,26 +2339,18 @@ pub fn get_opt_add_remove_edges(
         ctl.add("base", &[], PRG_FOUNDEDNESS)?;
     }
     if setting.ep {
-        panic!(
-            "error query.get_opt_add_remove_edges should not be called with
-          elementary path constraint, use instead
-          get_opt_add_remove_edges_greedy"
-        );
+        ctl.add("base", &[], PRG_ELEM_PATH)?;
     }
 
     ctl.add("base", &[], PRG_REMOVE_EDGES)?;
     ctl.add("base", &[], PRG_ADD_EDGES)?;
     ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
 
-    // ground & solve
-    let part = Part::new("base", vec![])?;
-    let parts = vec![part];
-
-    ctl.ground(&parts)?;
+    add_user_programs(&mut ctl, setting)?;
 
-    // solve
-    let mut handle = ctl.solve(SolveMode::YIELD, &[])?;
-    let cost = get_optimum(&mut handle)?;
+    // ground & solve
+    let handle = ground_and_solve(ctl, "opt add remove edges")?;
+    let cost = get_optimum(handle)?;
     Ok((cost[0], cost[1]))
 }
 
@@ -1326,6 +2363,7 @@ pub fn get_opt_repairs_add_remove_edges(
     max_solutions: u32,
     setting: &Setting,
 ) -> Result<Vec<std::vec::Vec<clingo::Symbol>>> {
+    check_exact_elem_path_size(graph, setting)?;
     // create a control object and pass command line arguments
     let mut ctl = clingo::control(vec![
         max_solutions.to_string(),
@@ -1363,12 +2401,11 @@ pub fn get_opt_repairs_add_remove_edges(
     ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
     ctl.add("base", &[], PRG_SHOW_REPAIRS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let ctl = ground(ctl)?;
-    let models = ctl.optimal_models()?;
-    models
-        .map(|model| extract_repairs(&model.symbols))
-        .collect()
+    let handle = ground_and_solve(ctl, "opt repairs add remove edges")?;
+    optimal_models(handle, extract_repairs)
 }
 
 pub fn get_opt_flip_edges(
@@ -1407,9 +2444,11 @@ pub fn get_opt_flip_edges(
     ctl.add("base", &[], PRG_FLIP_EDGE_DIRECTIONS)?;
     ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    let cost = get_optimum(&mut handle)?;
+    let handle = ground_and_solve(ctl, "opt flip edges")?;
+    let cost = get_optimum(handle)?;
     Ok((cost[0], cost[1]))
 }
 
@@ -1457,10 +2496,196 @@ pub fn get_opt_repairs_flip_edges(
     ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
     ctl.add("base", &[], PRG_SHOW_FLIP)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
+    // ground & solve
+    let handle = ground_and_solve(ctl, "opt repairs flip edges")?;
+    optimal_models(handle, extract_flips)
+}
+
+/// Returns the optimal scenfit and the minimal number of edges whose sign must be flipped
+pub fn get_opt_flip_edge_signs(
+    graph: &FactBase,
+    profiles: &FactBase,
+    inputs: &FactBase,
+    setting: &Setting,
+) -> Result<(i64, i64)> {
+    // create a control object and pass command line arguments
+    let mut ctl = clingo::control(vec!["--opt-strategy=5".to_string()])?;
+
+    ctl.add_facts(graph)?;
+    ctl.add_facts(profiles)?;
+    ctl.add_facts(inputs)?;
+
+    ctl.add("base", &[], PRG_SIGN_CONS)?;
+    ctl.add("base", &[], PRG_BWD_PROP)?;
+
+    ctl.add("base", &[], PRG_ERROR_MEASURE)?;
+    ctl.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
+    ctl.add("base", &[], PRG_KEEP_INPUTS)?;
+
+    if setting.os {
+        ctl.add("base", &[], PRG_ONE_STATE)?;
+    }
+    if setting.fp {
+        ctl.add("base", &[], PRG_FWD_PROP)?;
+    }
+    if setting.fc {
+        ctl.add("base", &[], PRG_FOUNDEDNESS)?;
+    }
+    if setting.ep {
+        ctl.add("base", &[], PRG_ELEM_PATH)?;
+    }
+
+    ctl.add("base", &[], PRG_FLIP_EDGE_SIGNS)?;
+    ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
+
+    add_user_programs(&mut ctl, setting)?;
+
+    // ground & solve
+    let handle = ground_and_solve(ctl, "opt flip edge signs")?;
+    let cost = get_optimum(handle)?;
+    Ok((cost[0], cost[1]))
+}
+
+/// Returns repair sets of edges with flipped sign that reach the optimal scenfit
+pub fn get_opt_repairs_flip_edge_signs(
+    graph: &FactBase,
+    profiles: &FactBase,
+    inputs: &FactBase,
+    scenfit: i64,
+    repair_score: i64,
+    max_solutions: u32,
+    setting: &Setting,
+) -> Result<Vec<std::vec::Vec<clingo::Symbol>>> {
+    let mut ctl = clingo::control(vec![
+        max_solutions.to_string(),
+        "--opt-strategy=5".to_string(),
+        "--project".to_string(),
+        format!("--opt-mode=optN,{scenfit},{repair_score}"),
+    ])?;
+
+    ctl.add_facts(graph)?;
+    ctl.add_facts(profiles)?;
+    ctl.add_facts(inputs)?;
+
+    ctl.add("base", &[], PRG_SIGN_CONS)?;
+    ctl.add("base", &[], PRG_BWD_PROP)?;
+
+    ctl.add("base", &[], PRG_ERROR_MEASURE)?;
+    ctl.add("base", &[], PRG_MIN_WEIGHTED_ERROR)?;
+    ctl.add("base", &[], PRG_KEEP_INPUTS)?;
+
+    if setting.os {
+        ctl.add("base", &[], PRG_ONE_STATE)?;
+    }
+    if setting.fp {
+        ctl.add("base", &[], PRG_FWD_PROP)?;
+    }
+    if setting.fc {
+        ctl.add("base", &[], PRG_FOUNDEDNESS)?;
+    }
+    if setting.ep {
+        ctl.add("base", &[], PRG_ELEM_PATH)?;
+    }
+
+    ctl.add("base", &[], PRG_FLIP_EDGE_SIGNS)?;
+    ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
+    ctl.add("base", &[], PRG_SHOW_FLIP_SIGN)?;
+
+    add_user_programs(&mut ctl, setting)?;
+
+    // ground & solve
+    let handle = ground_and_solve(ctl, "opt repairs flip edge signs")?;
+    optimal_models(handle, extract_sign_flips)
+}
+
+/// adds the programs of the combined repair mode
+fn add_combined_repairs(ctl: &mut Control, setting: &Setting) -> Result<()> {
+    ctl.add("base", &[], PRG_SIGN_CONS)?;
+    ctl.add("base", &[], PRG_BWD_PROP)?;
+
+    ctl.add("base", &[], PRG_ERROR_MEASURE)?;
+    ctl.add("base", &[], PRG_KEEP_INPUTS)?;
+
+    if setting.os {
+        ctl.add("base", &[], PRG_ONE_STATE)?;
+    }
+    if setting.fp {
+        ctl.add("base", &[], PRG_FWD_PROP)?;
+    }
+    if setting.fc {
+        ctl.add("base", &[], PRG_FOUNDEDNESS)?;
+    }
+    if setting.ep {
+        ctl.add("base", &[], PRG_ELEM_PATH)?;
+    }
+
+    ctl.add("base", &[], PRG_REMOVE_EDGES)?;
+    ctl.add("base", &[], PRG_FLIP_EDGE_DIRECTIONS)?;
+    ctl.add("base", &[], PRG_FLIP_EDGE_SIGNS)?;
+    ctl.add("base", &[], PRG_ADD_EDGES)?;
+    ctl.add("base", &[], PRG_ADD_INFLUENCES)?;
+    ctl.add("base", &[], PRG_MIN_COMBINED_REPAIRS)?;
+
+    add_user_programs(ctl, setting)
+}
+
+/// Returns the minimal total cost of a repair set that combines removing, flipping, sign flipping and adding edges,
+/// adding influences and discarding observations
+pub fn get_opt_combined_repairs(
+    graph: &FactBase,
+    profiles: &FactBase,
+    inputs: &FactBase,
+    costs: &RepairCosts,
+    setting: &Setting,
+) -> Result<i64> {
+    check_exact_elem_path_size(graph, setting)?;
+    // create a control object and pass command line arguments
+    let mut arguments = vec!["--opt-strategy=5".to_string()];
+    arguments.extend(costs.clingo_arguments());
+    let mut ctl = clingo::control(arguments)?;
+
+    ctl.add_facts(graph)?;
+    ctl.add_facts(profiles)?;
+    ctl.add_facts(inputs)?;
+    add_combined_repairs(&mut ctl, setting)?;
+
+    // ground & solve
+    let handle = ground_and_solve(ctl, "opt combined repairs")?;
+    Ok(get_optimum(handle)?[0])
+}
+
+/// Returns repair sets of the combined repair mode with the given total cost
+pub fn get_opt_repairs_combined(
+    graph: &FactBase,
+    profiles: &FactBase,
+    inputs: &FactBase,
+    costs: &RepairCosts,
+    cost: i64,
+    max_solutions: u32,
+    setting: &Setting,
+) -> Result<Vec<std::vec::Vec<clingo::Symbol>>> {
+    check_exact_elem_path_size(graph, setting)?;
+    let mut arguments = vec![
+        max_solutions.to_string(),
+        "--opt-strategy=5".to_string(),
+        "--project".to_string(),
+        format!("--opt-mode=optN,{cost}"),
+    ];
+    arguments.extend(costs.clingo_arguments());
+    let mut ctl = clingo::control(arguments)?;
+
+    ctl.add_facts(graph)?;
+    ctl.add_facts(profiles)?;
+    ctl.add_facts(inputs)?;
+    add_combined_repairs(&mut ctl, setting)?;
+    ctl.add("base", &[], PRG_SHOW_COMBINED_REPAIRS)?;
+    ctl.add("base", &[], PRG_SHOW_ERRORS)?;
+
     // ground & solve
-    let ctl = ground(ctl)?;
-    let models = ctl.optimal_models()?;
-    models.map(|model| extract_flips(&model.symbols)).collect()
+    let handle = ground_and_solve(ctl, "opt repairs combined")?;
+    optimal_models(handle, extract_combined_repairs)
 }
 
 pub fn get_opt_remove_edges(
@@ -1499,9 +2724,11 @@ pub fn get_opt_remove_edges(
     ctl.add("base", &[], PRG_REMOVE_EDGES)?;
     ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let mut handle = ground_and_solve(ctl)?;
-    let cost = get_optimum(&mut handle)?;
+    let handle = ground_and_solve(ctl, "opt remove edges")?;
+    let cost = get_optimum(handle)?;
     Ok((cost[0], cost[1]))
 }
 pub fn get_opt_repairs_remove_edges(
@@ -1548,32 +2775,26 @@ pub fn get_opt_repairs_remove_edges(
     ctl.add("base", &[], PRG_MIN_WEIGHTED_REPAIRS)?;
     ctl.add("base", &[], PRG_SHOW_REPAIRS)?;
 
+    add_user_programs(&mut ctl, setting)?;
+
     // ground & solve
-    let ctl = ground(ctl)?;
-    let models = ctl.optimal_models()?;
-    models
-        .map(|model| extract_repairs(&model.symbols))
-        .collect()
+    let handle = ground_and_solve(ctl, "opt repairs remove edges")?;
+    optimal_models(handle, extract_repairs)
 }
-/// Given a model this function returns a vector of mics
-fn extract_mics(symbols: &[Symbol]) -> Result<Vec<Symbol>> {
-    let mut mics = vec![];
-    for symbol in symbols {
-        match symbol.name()? {
-            "active" => {
-                let id = symbol.arguments()?[0];
-                mics.push(id);
-            }
-            _ => continue,
-        }
-    }
-    Ok(mics)
-}
-
 /// Given a model this function returns a vector of pairs (node,label)
 /// and a vector of repair operations needed to make the labeling consistent
 fn extract_labels_repairs(symbols: &[Symbol]) -> Result<(Labelings, Vec<RepairOp>)> {
-    let mut vlabels = vec![];
+    let (labels, repairs) = extract_experiment_labels_repairs(symbols)?;
+    let vlabels = labels.into_iter().flat_map(|(_, l)| l).collect();
+    Ok((vlabels, repairs))
+}
+
+/// Given a model this function returns the pairs (node,label) of each experiment
+/// and a vector of repair operations needed to make the labeling consistent
+fn extract_experiment_labels_repairs(
+    symbols: &[Symbol],
+) -> Result<(ExperimentLabels, Vec<RepairOp>)> {
+    let mut vlabels: ExperimentLabels = vec![];
     let mut err = vec![];
     for symbol in symbols {
         match symbol.name()? {
@@ -1581,11 +2802,16 @@ fn extract_labels_repairs(symbols: &[Symbol]) -> Result<(Labelings, Vec<RepairOp
                 let id = symbol.arguments()?[1];
                 // only return or nodes
                 if id.name()? == "or" {
+                    let experiment = symbol.arguments()?[0].string()?.to_string();
                     let behavior = into_behavior(symbol.arguments()?[2])?;
-                    vlabels.push(Prediction {
+                    let prediction = Prediction {
                         node: id.arguments()?[0].string()?.to_string(),
                         behavior,
-                    });
+                    };
+                    match vlabels.iter_mut().find(|(e, _)| *e == experiment) {
+                        Some((_, labels)) => labels.push(prediction),
+                        None => vlabels.push((experiment, vec![prediction])),
+                    }
                 }
             }
             "flip_node_sign_Plus_to_0" => {
@@ -1612,6 +2838,9 @@ fn extract_labels_repairs(symbols: &[Symbol]) -> Result<(Labelings, Vec<RepairOp
             "flip_node_sign_notMinus_to_Minus" => {
                 err.push(into_repair(symbol)?);
             }
+            "flip_node_sign_change_to_0" => {
+                err.push(into_repair(symbol)?);
+            }
             "addedge" => {
                 err.push(into_repair(symbol)?);
             }
@@ -1627,6 +2856,32 @@ fn extract_labels_repairs(symbols: &[Symbol]) -> Result<(Labelings, Vec<RepairOp
     Ok((vlabels, err))
 }
 
+/// Given a model of the combined repair mode this function returns the symbols
+/// of the repair operations and the discarded observations
+fn extract_combined_repairs(symbols: &[Symbol]) -> Result<Vec<Symbol>> {
+    let mut rep = vec![];
+    for symbol in symbols {
+        match symbol.name()? {
+            "addedge" | "remedge" | "flip" | "flip_edge_sign" | "new_influence" => {
+                rep.push(*symbol);
+            }
+            "flip_node_sign_Plus_to_0"
+            | "flip_node_sign_Plus_to_Minus"
+            | "flip_node_sign_Minus_to_0"
+            | "flip_node_sign_Minus_to_Plus"
+            | "flip_node_sign_0_to_Plus"
+            | "flip_node_sign_0_to_Minus"
+            | "flip_node_sign_notMinus_to_Minus"
+            | "flip_node_sign_notPlus_to_Plus"
+            | "flip_node_sign_change_to_0" => {
+                rep.push(*symbol);
+            }
+            _ => continue,
+        }
+    }
+    Ok(rep)
+}
+
 /// Given a model this function returns a vector of symbols
 /// denoting repair operations needed to make the labeling consistent
 fn extract_repairs(symbols: &[Symbol]) -> Result<Vec<Symbol>> {
@@ -1664,6 +2919,36 @@ fn extract_flips(symbols: &[Symbol]) -> Result<Vec<Symbol>> {
     }
     Ok(rep)
 }
+/// Given a model this function returns a vector of symbols
+/// denoting edges whose sign is flipped
+fn extract_sign_flips(symbols: &[Symbol]) -> Result<Vec<Symbol>> {
+    let mut rep = vec![];
+    for symbol in symbols {
+        if symbol.name()? == "flip_edge_sign" {
+            rep.push(*symbol);
+        }
+    }
+    Ok(rep)
+}
+/// Returns the mcos (or scenfit) together with the predictions under mcos (or scenfit)
+pub(crate) fn consistency_and_predictions(
+    graph: &FactBase,
+    profile: &FactBase,
+    inputs: &FactBase,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<(i64, Predictions)> {
+    if scenfit {
+        let scenfit = get_scenfit(graph, profile, inputs, setting)?;
+        let predictions = get_predictions_under_scenfit(graph, profile, inputs, setting)?;
+        Ok((scenfit, predictions))
+    } else {
+        let mcos = get_mcos(graph, profile, inputs, setting)?;
+        let predictions = get_predictions_under_mcos(graph, profile, inputs, setting)?;
+        Ok((mcos, predictions))
+    }
+}
+
 type Predictions = Vec<Prediction>;
 
 #[derive(Debug, Clone, Serialize)]
@@ -1676,6 +2961,22 @@ impl fmt::Display for Prediction {
         write!(f, "{} = {}", self.node, self.behavior)
     }
 }
+/// Given a model this function returns the Predictions of each experiment
+fn extract_experiment_predictions(symbols: &[Symbol]) -> Result<Vec<(ProfileId, Predictions)>> {
+    let mut experiments: Vec<(ProfileId, Vec<Symbol>)> = vec![];
+    for symbol in symbols {
+        let experiment = symbol.arguments()?[0].string()?.to_string();
+        match experiments.iter_mut().find(|(e, _)| *e == experiment) {
+            Some((_, symbols)) => symbols.push(*symbol),
+            None => experiments.push((experiment, vec![*symbol])),
+        }
+    }
+    experiments
+        .into_iter()
+        .map(|(e, symbols)| Ok((e, extract_predictions(&symbols)?)))
+        .collect()
+}
+
 /// Given a model this function returns a Vector of Predictions
 fn extract_predictions(symbols: &[Symbol]) -> Result<Predictions> {
     let mut predictions = Vec::new();
@@ -0,0 +1,252 @@
+use crate::cif_parser::{parse_statement, Expression, SNode};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+    /// the line is not a valid CIF statement
+    ParseError,
+    /// a node without name, e.g. `a & -> b`
+    EmptyName,
+    /// an edge from a node to itself
+    SelfLoop,
+    /// an activation and an inhibition between the same nodes, handled as `error_edge`,
+    /// or a member of AND nodes with the same members and target with both signs
+    OppositeSigns,
+    /// an AND node with only one distinct member
+    SingleMemberAnd,
+    /// a node without edges to other nodes
+    IsolatedNode,
+    /// node names that differ only by case
+    CaseConflict,
+}
+
+/// A structural problem in a network file
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub line: usize,
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub message: String,
+}
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}: {}", self.line, self.severity, self.message)
+    }
+}
+
+/// Checks a network file in CIF format for structural problems
+pub fn lint(file: &File) -> Result<Vec<LintIssue>> {
+    let mut lines = vec![];
+    for line in BufReader::new(file).lines() {
+        lines.push(line?);
+    }
+    Ok(lint_lines(&lines))
+}
+
+/// Checks the lines of a network in CIF format for structural problems.
+/// The issues are sorted by line number.
+pub fn lint_lines<S: AsRef<str>>(lines: &[S]) -> Vec<LintIssue> {
+    let mut issues = vec![];
+    // (start, target) -> (line, negated)
+    let mut edges: HashMap<(String, String), Vec<(usize, bool)>> = HashMap::new();
+    // (members, target) -> (line, member -> negated)
+    let mut and_edges: HashMap<(String, String), Vec<(usize, MemberSigns)>> = HashMap::new();
+    // node -> first line and whether it has an edge to another node
+    let mut nodes: BTreeMap<String, (usize, bool)> = BTreeMap::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let number = i + 1;
+        let line = line.as_ref().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let statement = match parse_statement(line) {
+            Ok(statement) => statement,
+            Err(e) => {
+                issues.push(LintIssue {
+                    line: number,
+                    severity: Severity::Error,
+                    kind: IssueKind::ParseError,
+                    message: format!("unable to parse '{line}': {e}"),
+                });
+                continue;
+            }
+        };
+        let target = statement.target();
+        let members: Vec<&Expression> = match statement.start() {
+            SNode::Single(expr) => vec![expr],
+            SNode::List(list) => list.iter().collect(),
+        };
+        let names: Vec<&str> = members.iter().map(|e| name(e)).collect();
+
+        if target.is_empty() || names.iter().any(|n| n.is_empty()) {
+            issues.push(LintIssue {
+                line: number,
+                severity: Severity::Error,
+                kind: IssueKind::EmptyName,
+                message: format!("node without name in '{line}'"),
+            });
+        }
+        for name in names.iter().filter(|n| **n == target) {
+            issues.push(LintIssue {
+                line: number,
+                severity: Severity::Warning,
+                kind: IssueKind::SelfLoop,
+                message: format!("{name} regulates itself"),
+            });
+        }
+        if let SNode::List(_) = statement.start() {
+            let distinct: BTreeSet<&str> =
+                names.iter().filter(|n| !n.is_empty()).copied().collect();
+            if let [member] = Vec::from_iter(distinct).as_slice() {
+                issues.push(LintIssue {
+                    line: number,
+                    severity: Severity::Warning,
+                    kind: IssueKind::SingleMemberAnd,
+                    message: format!("AND node with the single member '{member}'"),
+                });
+            }
+        }
+        if let SNode::List(list) = statement.start() {
+            // members of a single AND node with both signs
+            let mut signs: BTreeMap<&str, bool> = BTreeMap::new();
+            let mut reported = BTreeSet::new();
+            for expr in list {
+                if let Some(negated) = negated(expr) {
+                    match signs.get(name(expr)) {
+                        Some(first) if *first != negated && reported.insert(name(expr)) => {
+                            issues.push(LintIssue {
+                                line: number,
+                                severity: Severity::Error,
+                                kind: IssueKind::OppositeSigns,
+                                message: format!(
+                                    "{} occurs with both signs in the AND node of {target}",
+                                    name(expr)
+                                ),
+                            });
+                        }
+                        Some(_) => {}
+                        None => {
+                            signs.insert(name(expr), negated);
+                        }
+                    }
+                }
+            }
+            // AND nodes of the same members and target that differ in the sign of a member
+            let key: BTreeSet<&str> = list.iter().map(name).collect();
+            let key = (Vec::from_iter(key).join(" & "), target.to_string());
+            let signs: MemberSigns = signs.into_iter().map(|(n, s)| (n.to_string(), s)).collect();
+            let seen = and_edges.entry(key).or_default();
+            for (first, other) in seen.iter() {
+                if let Some((member, _)) = signs
+                    .iter()
+                    .find(|(member, negated)| other.get(*member).is_some_and(|n| n != *negated))
+                {
+                    issues.push(LintIssue {
+                        line: number,
+                        severity: Severity::Warning,
+                        kind: IssueKind::OppositeSigns,
+                        message: format!(
+                            "{member} has the opposite sign in the AND node of {target} in line {first}"
+                        ),
+                    });
+                    break;
+                }
+            }
+            seen.push((number, signs));
+        }
+        if let SNode::Single(expr) = statement.start() {
+            if let Some(negated) = negated(expr) {
+                let key = (name(expr).to_string(), target.to_string());
+                let seen = edges.entry(key).or_default();
+                if let Some((first, _)) = seen.iter().find(|(_, n)| *n != negated) {
+                    issues.push(LintIssue {
+                        line: number,
+                        severity: Severity::Error,
+                        kind: IssueKind::OppositeSigns,
+                        message: format!(
+                            "{} -> {target} has the opposite sign in line {first}",
+                            name(expr)
+                        ),
+                    });
+                }
+                seen.push((number, negated));
+            }
+        }
+
+        for node in names.iter().chain([&target]) {
+            let connected = names.iter().any(|n| n != node) || target != *node;
+            let entry = nodes.entry(node.to_string()).or_insert((number, false));
+            entry.1 |= connected;
+        }
+    }
+
+    let mut spellings: BTreeMap<String, Vec<(&str, usize)>> = BTreeMap::new();
+    for (node, (line, connected)) in &nodes {
+        if !connected && !node.is_empty() {
+            issues.push(LintIssue {
+                line: *line,
+                severity: Severity::Warning,
+                kind: IssueKind::IsolatedNode,
+                message: format!("{node} has no edges to other nodes"),
+            });
+        }
+        spellings
+            .entry(node.to_lowercase())
+            .or_default()
+            .push((node, *line));
+    }
+    for names in spellings.values().filter(|s| s.len() > 1) {
+        let (first, first_line) = names.iter().min_by_key(|(_, l)| *l).unwrap();
+        for (node, line) in names.iter().filter(|(n, _)| n != first) {
+            issues.push(LintIssue {
+                line: *line,
+                severity: Severity::Warning,
+                kind: IssueKind::CaseConflict,
+                message: format!("{node} differs only by case from {first} in line {first_line}"),
+            });
+        }
+    }
+
+    issues.sort_by_key(|i| i.line);
+    issues
+}
+
+/// the members of an AND node and whether they are negated
+type MemberSigns = BTreeMap<String, bool>;
+
+/// Returns whether the expression is negated, None for unknown influences
+fn negated(expr: &Expression) -> Option<bool> {
+    match expr {
+        Expression::Plain(_) => Some(false),
+        Expression::Negated(_) => Some(true),
+        Expression::Unknown(_) => None,
+    }
+}
+
+fn name(expr: &Expression) -> &str {
+    match expr {
+        Expression::Plain(s) | Expression::Negated(s) | Expression::Unknown(s) => s,
+    }
+}
@@ -0,0 +1,374 @@
+use crate::profile_parser::{Behavior, Observation, Profile};
+use crate::NodeId;
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// The discretized treatments of a MIDAS file
+#[derive(Debug, Clone)]
+pub struct Midas {
+    stimuli: Vec<String>,
+    treatments: Vec<Treatment>,
+}
+
+#[derive(Debug, Clone)]
+struct Treatment {
+    name: String,
+    /// applied stimuli
+    stimuli: Vec<String>,
+    /// applied inhibitors
+    inhibitors: Vec<String>,
+    observations: Vec<Observation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cue {
+    Stimulus,
+    Inhibitor,
+    /// e.g. the cell line
+    Other,
+}
+
+/// A treatment column, `TR:name` or `TR:name:type`
+struct TrColumn {
+    index: usize,
+    /// the column name as used in the treatment names
+    label: String,
+    /// the node that is stimulated or inhibited
+    node: String,
+    cue: Cue,
+}
+
+/// A row of measurements
+struct Row {
+    applied: Vec<bool>,
+    /// the time and value of each readout, `None` for missing values
+    values: Vec<(f64, Option<f64>)>,
+}
+
+impl Midas {
+    /// the names of the treatments, e.g. `egf+pi3ki`
+    pub fn treatments(&self) -> Vec<&str> {
+        self.treatments.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    /// Returns the profile of the treatment with the given index.
+    ///
+    /// All stimuli are inputs, increased if applied and unchanged otherwise.
+    /// Applied inhibitors are inputs with a forced decrease.
+    pub fn profile(&self, treatment: usize, id: &str) -> Profile {
+        let treatment = &self.treatments[treatment];
+        let mut profile = Profile::new(id);
+        for stimulus in &self.stimuli {
+            let behavior = if treatment.stimuli.contains(stimulus) {
+                Behavior::Plus
+            } else {
+                Behavior::Zero
+            };
+            add_input(&mut profile, stimulus, behavior);
+        }
+        for inhibitor in &treatment.inhibitors {
+            add_input(&mut profile, inhibitor, Behavior::Minus);
+        }
+        for observation in &treatment.observations {
+            if !profile.inputs.contains(&observation.node) {
+                profile.observations.push(observation.clone());
+            }
+        }
+        profile
+    }
+}
+
+fn add_input(profile: &mut Profile, node: &str, behavior: Behavior) {
+    let node = NodeId::Or(node.to_string());
+    if !profile.inputs.contains(&node) {
+        profile.inputs.push(node.clone());
+        profile.observations.push(Observation { node, behavior });
+    }
+}
+
+/// Reads experiments in MIDAS format and discretizes the readouts.
+///
+/// The columns `TR:name` are treatments: stimuli, or inhibitors if the column has the type `Inhibitors`
+/// or, without type, the name ends with `i` (the inhibited node is the name without the `i`),
+/// which is reported as warning.
+/// Columns of other types like `TR:HEK293:CellLine` are ignored.
+/// The columns `DA:` contain the time of the measurements, `DV:name` the value of the readout `name`.
+///
+/// Every combination of applied stimuli and inhibitors is a treatment.
+/// The values at the latest time point of a treatment are compared with the control at the same time
+/// (or else at its earliest time), the rows without stimuli and inhibitors,
+/// or the rows at the earliest time if there are none.
+/// A readout is increased or decreased if the mean values differ by more than `threshold`, otherwise unchanged.
+/// Returns the treatments together with warnings about missing data.
+pub fn read(file: &File, threshold: f64) -> Result<(Midas, Vec<String>)> {
+    let mut warnings = vec![];
+    let mut lines = BufReader::new(file).lines();
+    let header = match lines.next() {
+        Some(header) => header?,
+        None => bail!("empty MIDAS file"),
+    };
+    let header: Vec<String> = split(&header);
+
+    let mut tr_columns = vec![];
+    let mut da_columns = vec![];
+    let mut dv_columns = vec![];
+    for (index, column) in header.iter().enumerate() {
+        if let Some(name) = column.strip_prefix("TR:") {
+            let (label, cue) = match name.split_once(':') {
+                Some((label, "Stimuli")) => (label, Cue::Stimulus),
+                Some((label, "Inhibitors")) => (label, Cue::Inhibitor),
+                Some((label, _)) => (label, Cue::Other),
+                None if name.ends_with('i') => {
+                    warnings.push(format!(
+                        "column {column} without type is read as inhibitor of {}, \
+                         add the type :Inhibitors or :Stimuli",
+                        name.strip_suffix('i').unwrap_or(name)
+                    ));
+                    (name, Cue::Inhibitor)
+                }
+                None => (name, Cue::Stimulus),
+            };
+            let node = match cue {
+                Cue::Inhibitor => label.strip_suffix('i').unwrap_or(label),
+                _ => label,
+            };
+            tr_columns.push(TrColumn {
+                index,
+                label: label.to_string(),
+                node: node.to_string(),
+                cue,
+            });
+        } else if let Some(name) = column.strip_prefix("DA:") {
+            da_columns.push((index, name.to_string()));
+        } else if let Some(name) = column.strip_prefix("DV:") {
+            dv_columns.push((index, name.to_string()));
+        }
+    }
+    if dv_columns.is_empty() {
+        bail!("no readout columns DV: in the MIDAS header");
+    }
+    let tr_columns: Vec<TrColumn> = tr_columns
+        .into_iter()
+        .filter(|c| c.cue != Cue::Other)
+        .collect();
+    // the time of a readout is given by its own DA column or by the common one
+    let mut time_columns = vec![];
+    for (_, readout) in &dv_columns {
+        let column = da_columns
+            .iter()
+            .find(|(_, name)| name == readout)
+            .or_else(|| da_columns.iter().find(|(_, name)| name == "ALL"))
+            .or_else(|| da_columns.first());
+        time_columns.push(column.map(|(index, _)| *index));
+    }
+
+    let mut rows = vec![];
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split(&line);
+        let field = |index: usize| fields.get(index).map(String::as_str).unwrap_or_default();
+        let applied = tr_columns
+            .iter()
+            .map(|c| value(field(c.index)).is_some_and(|v| v > 0.0))
+            .collect();
+        let mut values = vec![];
+        for ((index, readout), time) in dv_columns.iter().zip(&time_columns) {
+            let time = time.and_then(|t| value(field(t))).unwrap_or(0.0);
+            let v = value(field(*index));
+            if v.is_none() && !is_missing(field(*index)) {
+                warnings.push(format!(
+                    "line {}: value '{}' of {readout} is ignored",
+                    i + 2,
+                    field(*index)
+                ));
+            }
+            values.push((time, v));
+        }
+        rows.push(Row { applied, values });
+    }
+
+    // rows without stimuli and inhibitors, or the rows at the earliest time
+    let mut control: Vec<&Row> = rows
+        .iter()
+        .filter(|r| r.applied.iter().all(|a| !a))
+        .collect();
+    if control.is_empty() {
+        let earliest = rows
+            .iter()
+            .flat_map(|r| r.values.iter().map(|(t, _)| *t))
+            .fold(f64::INFINITY, f64::min);
+        control = rows
+            .iter()
+            .filter(|r| r.values.iter().all(|(t, _)| *t == earliest))
+            .collect();
+        if control.is_empty() {
+            bail!("no control rows without treatment or at the earliest time");
+        }
+        warnings.push(format!(
+            "no rows without treatment, the rows at time {earliest} are used as control"
+        ));
+    }
+
+    let mut groups: BTreeMap<&[bool], Vec<&Row>> = BTreeMap::new();
+    for row in &rows {
+        if row.applied.iter().any(|a| *a) {
+            groups.entry(&row.applied).or_default().push(row);
+        }
+    }
+    let mut treatments = vec![];
+    let mut several_times = false;
+    for (applied, group) in groups {
+        let columns: Vec<&TrColumn> = tr_columns
+            .iter()
+            .zip(applied)
+            .filter(|(_, a)| **a)
+            .map(|(c, _)| c)
+            .collect();
+        let name = columns
+            .iter()
+            .map(|c| c.label.as_str())
+            .collect::<Vec<_>>()
+            .join("+");
+        let nodes = |cue: Cue| -> Vec<String> {
+            columns
+                .iter()
+                .filter(|c| c.cue == cue)
+                .map(|c| c.node.clone())
+                .collect()
+        };
+        let mut observations = vec![];
+        for (r, (_, readout)) in dv_columns.iter().enumerate() {
+            let latest = group
+                .iter()
+                .filter(|row| row.values[r].1.is_some())
+                .map(|row| row.values[r].0)
+                .fold(f64::NEG_INFINITY, f64::max);
+            if group
+                .iter()
+                .any(|row| row.values[r].1.is_some() && row.values[r].0 != latest)
+            {
+                several_times = true;
+            }
+            let treated = mean(
+                group
+                    .iter()
+                    .copied()
+                    .filter(|row| row.values[r].0 == latest),
+                r,
+            );
+            // the control at the same time, or else at its earliest time
+            let baseline = mean(
+                control
+                    .iter()
+                    .copied()
+                    .filter(|row| row.values[r].0 == latest),
+                r,
+            )
+            .or_else(|| {
+                let earliest = control
+                    .iter()
+                    .filter(|row| row.values[r].1.is_some())
+                    .map(|row| row.values[r].0)
+                    .fold(f64::INFINITY, f64::min);
+                mean(
+                    control
+                        .iter()
+                        .copied()
+                        .filter(|row| row.values[r].0 == earliest),
+                    r,
+                )
+            });
+            let (treated, baseline) = match (treated, baseline) {
+                (Some(treated), Some(baseline)) => (treated, baseline),
+                (Some(_), None) => {
+                    warnings.push(format!(
+                        "{readout}: no control value, the readout is ignored"
+                    ));
+                    continue;
+                }
+                (None, _) => continue,
+            };
+            let behavior = if treated - baseline > threshold {
+                Behavior::Plus
+            } else if baseline - treated > threshold {
+                Behavior::Minus
+            } else {
+                Behavior::Zero
+            };
+            observations.push(Observation {
+                node: NodeId::Or(readout.clone()),
+                behavior,
+            });
+        }
+        treatments.push(Treatment {
+            name,
+            stimuli: nodes(Cue::Stimulus),
+            inhibitors: nodes(Cue::Inhibitor),
+            observations,
+        });
+    }
+    if several_times {
+        warnings.push("only the latest time point of each treatment is used".to_string());
+    }
+    warnings.sort();
+    warnings.dedup();
+    let mut stimuli: Vec<String> = tr_columns
+        .iter()
+        .filter(|c| c.cue == Cue::Stimulus)
+        .map(|c| c.node.clone())
+        .collect();
+    stimuli.dedup();
+    Ok((
+        Midas {
+            stimuli,
+            treatments,
+        },
+        warnings,
+    ))
+}
+
+/// splits a CSV line and removes quotes
+/// splits a CSV line into its fields,
+/// commas inside double quotes are part of the field and `""` is a quote
+fn split(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+fn is_missing(field: &str) -> bool {
+    matches!(field, "" | "NA" | "NaN" | "nan")
+}
+
+fn value(field: &str) -> Option<f64> {
+    field.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// the mean value of the readout with index `r` in the rows
+fn mean<'a>(rows: impl Iterator<Item = &'a Row>, r: usize) -> Option<f64> {
+    let values: Vec<f64> = rows.filter_map(|row| row.values[r].1).collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
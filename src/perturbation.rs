@@ -0,0 +1,283 @@
+use crate::cif_parser::Graph;
+use crate::profile_parser::{Behavior, Observation, Profile};
+use crate::{consistency_and_predictions, NodeId, Setting, UserProgram};
+use anyhow::{anyhow, Result};
+use clingo::{FactBase, ToSymbol};
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// An in-silico intervention on the network or the experiment
+#[derive(Debug, Clone)]
+pub enum Intervention {
+    /// fix the behavior of a node independent of its regulators, e.g. `fix:mtor=-`
+    Fix { node: NodeId, behavior: Behavior },
+    /// knock out a node with all its edges, e.g. `remove:mtor`
+    Remove(NodeId),
+    /// cut the edge between two nodes, e.g. `cut:pi3k->akt`
+    Cut { start: NodeId, target: NodeId },
+    /// declare a node as new input, e.g. `input:egf`
+    Input(NodeId),
+}
+impl fmt::Display for Intervention {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Intervention::Fix { node, behavior } => write!(f, "fix:{node}={behavior}"),
+            Intervention::Remove(node) => write!(f, "remove:{node}"),
+            Intervention::Cut { start, target } => write!(f, "cut:{start}->{target}"),
+            Intervention::Input(node) => write!(f, "input:{node}"),
+        }
+    }
+}
+impl Serialize for Intervention {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+impl FromStr for Intervention {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, arg) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("missing ':' in intervention '{s}'"))?;
+        let node = |name: &str| {
+            if name.is_empty() {
+                Err(anyhow!("missing node name in intervention '{s}'"))
+            } else {
+                Ok(NodeId::Or(name.to_string()))
+            }
+        };
+        match kind {
+            "fix" => {
+                let (name, value) = arg
+                    .rsplit_once('=')
+                    .ok_or_else(|| anyhow!("expected 'fix:NODE=+', '-' or '0', found '{s}'"))?;
+                let behavior = match value {
+                    "+" => Behavior::Plus,
+                    "-" => Behavior::Minus,
+                    "0" => Behavior::Zero,
+                    _ => Err(anyhow!("expected '+', '-' or '0' in intervention '{s}'"))?,
+                };
+                Ok(Intervention::Fix {
+                    node: node(name)?,
+                    behavior,
+                })
+            }
+            "remove" => Ok(Intervention::Remove(node(arg)?)),
+            "cut" => {
+                let (start, target) = arg
+                    .split_once("->")
+                    .ok_or_else(|| anyhow!("expected 'cut:START->TARGET', found '{s}'"))?;
+                Ok(Intervention::Cut {
+                    start: node(start)?,
+                    target: node(target)?,
+                })
+            }
+            "input" => Ok(Intervention::Input(node(arg)?)),
+            _ => Err(anyhow!(
+                "unknown intervention '{kind}', expected fix, remove, cut or input"
+            )),
+        }
+    }
+}
+
+/// Prediction of a node before and after the interventions
+#[derive(Debug, Clone, Serialize)]
+pub struct PredictionChange {
+    pub node: String,
+    pub before: Option<Behavior>,
+    pub after: Option<Behavior>,
+}
+impl fmt::Display for PredictionChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |b: &Option<Behavior>| match b {
+            Some(b) => b.to_string(),
+            None => "none".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.node,
+            show(&self.before),
+            show(&self.after)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerturbationResult {
+    pub interventions: Vec<Intervention>,
+    /// mcos (or scenfit) without the interventions
+    pub consistency_before: i64,
+    /// mcos (or scenfit) with the interventions
+    pub consistency_after: i64,
+    /// nodes whose prediction differs
+    pub changes: Vec<PredictionChange>,
+}
+impl PerturbationResult {
+    pub fn print(&self) {
+        let interventions: Vec<String> = self.interventions.iter().map(|i| i.to_string()).collect();
+        println!("\nInterventions: {}", interventions.join(", "));
+        println!(
+            "Inconsistency before: {}, after: {}",
+            self.consistency_before, self.consistency_after
+        );
+        if self.changes.is_empty() {
+            println!("\nThe interventions do not change the predictions.");
+        } else {
+            println!("\nChanged predictions:\n");
+            for change in &self.changes {
+                println!("- {change}");
+            }
+        }
+    }
+}
+
+/// Applies the interventions to the graph, the profile and the input facts.
+///
+/// Fixing a node removes its incoming edges, declares it as input and observes the fixed behavior.
+/// The fixed behavior itself is enforced by [`fix_constraints`].
+/// Removing a node also removes its observations and inputs.
+pub fn apply_interventions(
+    graph: &Graph,
+    profile: &Profile,
+    inputs: &FactBase,
+    interventions: &[Intervention],
+) -> Result<(Graph, Profile, FactBase)> {
+    let mut graph = graph.clone();
+    let mut profile = profile.clone();
+    let mut removed = vec![];
+    for intervention in interventions {
+        match intervention {
+            Intervention::Fix { node, behavior } => {
+                check_node(&graph, node)?;
+                graph.remove_incoming_edges(node);
+                profile.observations.retain(|o| &o.node != node);
+                profile.observations.push(Observation {
+                    node: node.clone(),
+                    behavior: *behavior,
+                });
+                if !profile.inputs.contains(node) {
+                    profile.inputs.push(node.clone());
+                }
+            }
+            Intervention::Remove(node) => {
+                if !graph.remove_node(node) {
+                    Err(anyhow!("unknown node '{node}'"))?;
+                }
+                profile.observations.retain(|o| &o.node != node);
+                profile.inputs.retain(|n| n != node);
+                profile.min.retain(|n| n != node);
+                profile.max.retain(|n| n != node);
+                removed.push(node.symbol()?);
+            }
+            Intervention::Cut { start, target } => {
+                if !graph.remove_edge(start, target) {
+                    Err(anyhow!("no edge from '{start}' to '{target}'"))?;
+                }
+            }
+            Intervention::Input(node) => {
+                check_node(&graph, node)?;
+                if !profile.inputs.contains(node) {
+                    profile.inputs.push(node.clone());
+                }
+            }
+        }
+    }
+    let mut new_inputs = FactBase::new();
+    for input in inputs.iter() {
+        if !removed.contains(&input.arguments()?[0]) {
+            new_inputs.insert(input);
+        }
+    }
+    Ok((graph, profile, new_inputs))
+}
+
+/// Hard constraints that force every fixed node to its fixed behavior in all experiments.
+///
+/// The observation added by [`apply_interventions`] alone is not enough, under scenfit it could
+/// be discarded at the cost of one error.
+pub fn fix_constraints(interventions: &[Intervention]) -> Result<Option<UserProgram>> {
+    let mut program = String::new();
+    for intervention in interventions {
+        if let Intervention::Fix { node, behavior } = intervention {
+            let node = node.symbol()?;
+            for sign in [1, -1, 0] {
+                if !behavior.signs().contains(&sign) {
+                    program.push_str(&format!(":- vlabel(E,{node},{sign}).\n"));
+                }
+            }
+        }
+    }
+    if program.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(UserProgram::new("fixed nodes", &program)?))
+}
+
+fn check_node(graph: &Graph, node: &NodeId) -> Result<()> {
+    if graph.or_nodes().contains(node) {
+        Ok(())
+    } else {
+        Err(anyhow!("unknown node '{node}'"))
+    }
+}
+
+/// Compares the predictions under mcos (or scenfit) with and without the interventions
+pub fn perturb(
+    graph: &Graph,
+    profile: &Profile,
+    inputs: &FactBase,
+    interventions: &[Intervention],
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<PerturbationResult> {
+    let (pgraph, pprofile, pinputs) = apply_interventions(graph, profile, inputs, interventions)?;
+    let mut psetting = setting.clone();
+    if let Some(program) = fix_constraints(interventions)? {
+        psetting.user_programs.push(program);
+    }
+
+    let (consistency_before, before) = consistency_and_predictions(
+        &graph.to_facts(),
+        &profile.to_facts(),
+        inputs,
+        scenfit,
+        setting,
+    )?;
+    let (consistency_after, after) = consistency_and_predictions(
+        &pgraph.to_facts(),
+        &pprofile.to_facts(),
+        &pinputs,
+        scenfit,
+        &psetting,
+    )?;
+
+    let mut nodes: BTreeMap<String, (Option<Behavior>, Option<Behavior>)> = BTreeMap::new();
+    for p in before {
+        nodes.entry(p.node).or_default().0 = Some(p.behavior);
+    }
+    for p in after {
+        nodes.entry(p.node).or_default().1 = Some(p.behavior);
+    }
+    let changes = nodes
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(node, (before, after))| PredictionChange {
+            node,
+            before,
+            after,
+        })
+        .collect();
+
+    Ok(PerturbationResult {
+        interventions: interventions.to_vec(),
+        consistency_before,
+        consistency_after,
+        changes,
+    })
+}
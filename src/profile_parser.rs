@@ -1,5 +1,5 @@
 use crate::{FactBase, NodeId, ToSymbol};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clingo::*;
 use serde::{Serialize, Serializer};
 use std::fmt;
@@ -14,6 +14,15 @@ pub struct Profile {
     pub min: Vec<NodeId>,
     pub max: Vec<NodeId>,
     pub observations: Vec<Observation>,
+    /// ordered time points of a time series, empty for a single change
+    pub time_points: Vec<TimePoint>,
+}
+/// Observations at a time point of a time series,
+/// the changes are relative to the baseline before the first time point
+#[derive(Debug, Clone)]
+pub struct TimePoint {
+    pub name: String,
+    pub observations: Vec<Observation>,
 }
 #[derive(Debug, Clone)]
 pub struct Observation {
@@ -21,7 +30,7 @@ pub struct Observation {
     pub behavior: Behavior,
 }
 pub type ProfileId = String;
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Behavior {
     Plus,
     Minus,
@@ -30,6 +39,23 @@ pub enum Behavior {
     NotMinus,
     Change,
 }
+impl Behavior {
+    /// the sign changes (1, -1, 0) that are allowed by the behavior
+    pub fn signs(&self) -> &'static [i8] {
+        match self {
+            Behavior::Plus => &[1],
+            Behavior::Minus => &[-1],
+            Behavior::Zero => &[0],
+            Behavior::NotPlus => &[-1, 0],
+            Behavior::NotMinus => &[1, 0],
+            Behavior::Change => &[1, -1],
+        }
+    }
+    /// returns true if both behaviors allow a common sign change
+    pub fn is_compatible(&self, other: &Behavior) -> bool {
+        self.signs().iter().any(|s| other.signs().contains(s))
+    }
+}
 impl fmt::Display for Behavior {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -82,6 +108,18 @@ pub struct ObsVLabel<'a> {
     behavior: Behavior,
 }
 
+#[derive(ToSymbol)]
+pub struct Exp<'a> {
+    profile: &'a ProfileId,
+}
+
+#[derive(ToSymbol)]
+pub struct Step<'a> {
+    previous: &'a ProfileId,
+    step: &'a ProfileId,
+    next: &'a ProfileId,
+}
+
 #[derive(ToSymbol)]
 pub struct IsMin<'a> {
     profile: &'a ProfileId,
@@ -95,30 +133,127 @@ pub struct IsMax<'a> {
 }
 
 impl Profile {
+    pub fn new(id: &str) -> Profile {
+        Profile {
+            id: id.to_string(),
+            inputs: vec![],
+            min: vec![],
+            max: vec![],
+            observations: vec![],
+            time_points: vec![],
+        }
+    }
+    /// returns the identifier of the profile
+    pub fn id(&self) -> &ProfileId {
+        &self.id
+    }
+    /// declares the nodes as inputs of the profile, in addition to its inputs
+    pub fn add_inputs(&mut self, nodes: &[NodeId]) {
+        for node in nodes {
+            if !self.inputs.contains(node) {
+                self.inputs.push(node.clone());
+            }
+        }
+    }
+    /// replaces the inputs of the profile
+    pub fn set_inputs(&mut self, nodes: &[NodeId]) {
+        self.inputs.clear();
+        self.add_inputs(nodes);
+    }
+    /// returns true if the profile is a time series
+    pub fn is_time_series(&self) -> bool {
+        !self.time_points.is_empty()
+    }
+    /// returns the experiment that compares the baseline with the time point
+    pub fn time_point_experiment(&self, time_point: &TimePoint) -> ProfileId {
+        format!("{}@{}", self.id, time_point.name)
+    }
+    /// returns the single change or the time points of a time series as pairs of experiment and observations
+    pub fn experiments(&self) -> Vec<(ProfileId, &[Observation])> {
+        if self.is_time_series() {
+            self.time_points
+                .iter()
+                .map(|t| (self.time_point_experiment(t), t.observations.as_slice()))
+                .collect()
+        } else {
+            vec![(self.id.clone(), self.observations.as_slice())]
+        }
+    }
+    /// returns the observations of the single change and of all time points
+    pub fn all_observations(&self) -> impl Iterator<Item = &Observation> {
+        self.observations
+            .iter()
+            .chain(self.time_points.iter().flat_map(|t| &t.observations))
+    }
+    /// returns the observed nodes and the nodes declared as input, min or max
+    pub fn nodes(&self) -> Vec<NodeId> {
+        let mut nodes: Vec<NodeId> = self.all_observations().map(|o| o.node.clone()).collect();
+        nodes.extend(self.inputs.iter().cloned());
+        nodes.extend(self.min.iter().cloned());
+        nodes.extend(self.max.iter().cloned());
+        nodes.sort();
+        nodes.dedup();
+        nodes
+    }
+    /// Returns the facts of the profile.
+    ///
+    /// Each time point of a time series is an experiment that compares the baseline with the time point,
+    /// and consecutive time points are linked by an experiment for the step between them.
+    /// Inputs hold in all experiments, MIN and MAX refer to the baseline.
     pub fn to_facts(&self) -> FactBase {
+        if !self.is_time_series() {
+            return self.experiment_facts(&self.id, &self.observations);
+        }
+        let mut facts = FactBase::new();
+        let mut previous: Option<ProfileId> = None;
+        for time_point in &self.time_points {
+            let experiment = self.time_point_experiment(time_point);
+            facts.union(&self.experiment_facts(&experiment, &time_point.observations));
+            if let Some(previous) = previous {
+                let step = format!("{previous}->{}", time_point.name);
+                for node in &self.inputs {
+                    facts.insert(&Input {
+                        profile: &step,
+                        node,
+                    });
+                }
+                facts.insert(&Step {
+                    previous: &previous,
+                    step: &step,
+                    next: &experiment,
+                });
+            }
+            facts.insert(&Exp {
+                profile: &experiment,
+            });
+            previous = Some(experiment);
+        }
+        facts
+    }
+    fn experiment_facts(&self, experiment: &ProfileId, observations: &[Observation]) -> FactBase {
         let mut facts = FactBase::new();
         for node in &self.inputs {
             facts.insert(&Input {
-                profile: &self.id,
+                profile: experiment,
                 node,
             });
         }
-        for obs in &self.observations {
+        for obs in observations {
             facts.insert(&ObsVLabel {
-                profile: &self.id,
+                profile: experiment,
                 node: &obs.node,
                 behavior: obs.behavior,
             });
         }
         for node in &self.min {
             facts.insert(&IsMin {
-                profile: &self.id,
+                profile: experiment,
                 node,
             });
         }
         for node in &self.max {
             facts.insert(&IsMax {
-                profile: &self.id,
+                profile: experiment,
                 node,
             });
         }
@@ -132,12 +267,32 @@ pub fn read(file: &File, id: &str) -> Result<Profile> {
     let mut observations = vec![];
     let mut min = vec![];
     let mut max = vec![];
+    let mut time_points: Vec<TimePoint> = vec![];
 
     for line in file.lines() {
         let l1 = line?;
         let l = l1.trim();
         if !l.is_empty() {
-            match profile::statement(l)? {
+            let statement = profile::statement(l)?;
+            if let PStatement::TimePoint(name) = &statement {
+                if time_points.iter().any(|t| t.name == *name) {
+                    bail!("time point {name} is declared twice");
+                }
+                if !observations.is_empty() {
+                    bail!("observations before the first time point {name}");
+                }
+                time_points.push(TimePoint {
+                    name: name.clone(),
+                    observations: vec![],
+                });
+                continue;
+            }
+            // observations belong to the current time point
+            let observations = match time_points.last_mut() {
+                Some(time_point) => &mut time_point.observations,
+                None => &mut observations,
+            };
+            match statement {
                 PStatement::Input(s) => {
                     inputs.push(NodeId::Or(s));
                 }
@@ -172,12 +327,19 @@ pub fn read(file: &File, id: &str) -> Result<Profile> {
                         behavior: Behavior::NotMinus,
                     });
                 }
+                PStatement::Change(s) => {
+                    observations.push(Observation {
+                        node: NodeId::Or(s),
+                        behavior: Behavior::Change,
+                    });
+                }
                 PStatement::Min(s) => {
                     min.push(NodeId::Or(s));
                 }
                 PStatement::Max(s) => {
                     max.push(NodeId::Or(s));
                 }
+                PStatement::TimePoint(_) => unreachable!(),
             }
         }
     }
@@ -187,6 +349,7 @@ pub fn read(file: &File, id: &str) -> Result<Profile> {
         observations,
         min,
         max,
+        time_points,
     })
 }
 
@@ -198,8 +361,10 @@ pub enum PStatement {
     Zero(String),
     NotPlus(String),
     NotMinus(String),
+    Change(String),
     Min(String),
     Max(String),
+    TimePoint(String),
 }
 
 peg::parser! {grammar profile() for str {
@@ -210,8 +375,10 @@ peg::parser! {grammar profile() for str {
     use super::PStatement::Zero;
     use super::PStatement::NotPlus;
     use super::PStatement::NotMinus;
+    use super::PStatement::Change;
     use super::PStatement::Min;
     use super::PStatement::Max;
+    use super::PStatement::TimePoint;
 
     rule whitespace() = quiet!{[' ' | '\t']+}
 
@@ -222,9 +389,13 @@ peg::parser! {grammar profile() for str {
         / s:ident() whitespace()+ "=" whitespace()+ "0" { Zero(s.to_string()) }
         / s:ident() whitespace()+ "=" whitespace()+ "notPlus" { NotPlus(s.to_string()) }
         / s:ident() whitespace()+ "=" whitespace()+ "notMinus" { NotMinus(s.to_string()) }
+        / s:ident() whitespace()+ "=" whitespace()+ "CHANGE" { Change(s.to_string()) }
         / s:ident() whitespace()+ "=" whitespace()+ "MIN" { Min(s.to_string()) }
         / s:ident() whitespace()+ "=" whitespace()+ "MAX" { Max(s.to_string()) }
+        / "@" whitespace()* s:$(ident_char()+) { TimePoint(s.to_string()) }
 
     pub rule ident() -> &'input str
-        = $(['a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':' | '-' | '[' | ']']*)
+        = $(ident_char()*)
+
+    rule ident_char() = ['a'..='z' | 'A'..='Z' | '0'..='9' | '_' | ':' | '-' | '[' | ']']
 }}
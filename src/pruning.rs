@@ -0,0 +1,141 @@
+use crate::cif_parser::Graph;
+use crate::profile_parser::Profile;
+use crate::{into_node_id, NodeId, Setting};
+use anyhow::Result;
+use clingo::FactBase;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Nodes removed by the relevance pruning
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Pruning {
+    /// nodes without a path to an observed node
+    pub unobservable: Vec<NodeId>,
+    /// nodes without a path from an input
+    pub unreachable: Vec<NodeId>,
+}
+impl Pruning {
+    pub fn print(&self) {
+        println!("\n## Network pruning\n");
+        println!(
+            "Removed nodes without a path to an observed node: {}",
+            self.unobservable.len()
+        );
+        for node in &self.unobservable {
+            println!("- {node}");
+        }
+        println!(
+            "\nRemoved nodes without a path from an input: {}",
+            self.unreachable.len()
+        );
+        for node in &self.unreachable {
+            println!("- {node}");
+        }
+    }
+}
+
+/// Removes the nodes that cannot affect the observations.
+///
+/// The values of nodes without a path to an observed node never restrict the observed nodes,
+/// these nodes are always removed.
+/// Nodes without a path from an input can only change through a repair, so they are only removed
+/// when computing the scenfit with foundedness constraints.
+/// An AND node is reachable only if all its inputs are reachable.
+/// The mcos and scenfit of the pruned network are the same as of the original network,
+/// as long as no repair adds edges or flips their direction
+/// and no user constraint refers to a removed node.
+pub fn prune(
+    graph: &Graph,
+    profiles: &[Profile],
+    inputs: &FactBase,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<(Graph, Pruning)> {
+    let edges: Vec<&(NodeId, NodeId)> = graph
+        .activations()
+        .iter()
+        .chain(graph.inhibitions())
+        .collect();
+    let nodes: BTreeSet<&NodeId> = graph.or_nodes().iter().chain(graph.and_nodes()).collect();
+    let observed: BTreeSet<NodeId> = profiles
+        .iter()
+        .flat_map(|p| {
+            p.all_observations()
+                .map(|o| o.node.clone())
+                .chain(p.min.iter().cloned())
+                .chain(p.max.iter().cloned())
+        })
+        .collect();
+
+    // nodes with a path to an observed node
+    let mut observable: BTreeSet<&NodeId> = nodes
+        .iter()
+        .filter(|n| observed.contains(n))
+        .copied()
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (s, t) in &edges {
+            if observable.contains(t) && observable.insert(s) {
+                changed = true;
+            }
+        }
+    }
+
+    // nodes with a path from an input
+    let reachable: Option<BTreeSet<&NodeId>> = if scenfit && setting.fc {
+        let mut input_nodes: BTreeSet<NodeId> = profiles
+            .iter()
+            .flat_map(|p| p.inputs.iter().cloned())
+            .collect();
+        for input in inputs.iter() {
+            input_nodes.insert(into_node_id(input.arguments()?[0])?);
+        }
+        let mut reachable: BTreeSet<&NodeId> = nodes
+            .iter()
+            .filter(|n| input_nodes.contains(n))
+            .copied()
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in &nodes {
+                if reachable.contains(node) {
+                    continue;
+                }
+                let mut regulators = edges.iter().filter(|(_, t)| &t == node);
+                let reached = match node {
+                    NodeId::Or(_) => regulators.any(|(s, _)| reachable.contains(s)),
+                    NodeId::And(_) => regulators.all(|(s, _)| reachable.contains(s)),
+                };
+                if reached {
+                    reachable.insert(node);
+                    changed = true;
+                }
+            }
+        }
+        Some(reachable)
+    } else {
+        None
+    };
+
+    let mut pruned = graph.clone();
+    let mut pruning = Pruning::default();
+    for node in &nodes {
+        if observed.contains(node) {
+            continue;
+        }
+        if !observable.contains(node) {
+            pruning.unobservable.push((*node).clone());
+        } else if let Some(reachable) = &reachable {
+            if !reachable.contains(node) {
+                pruning.unreachable.push((*node).clone());
+            }
+        }
+    }
+    for node in pruning.unobservable.iter().chain(&pruning.unreachable) {
+        pruned.remove_node(node);
+    }
+    Ok((pruned, pruning))
+}
@@ -0,0 +1,202 @@
+use crate::profile_parser::{Behavior, Observation, Profile};
+use crate::NodeId;
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// How disagreeing replicate observations of a node are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergePolicy {
+    /// keep the behavior observed in most replicates, drop the node on a tie
+    Majority,
+    /// keep the strongest behavior that agrees with all replicates,
+    /// e.g. `notPlus` for `-` and `0`, drop the node if there is none
+    Downgrade,
+    /// drop the node
+    Drop,
+}
+impl FromStr for MergePolicy {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "majority" => Ok(MergePolicy::Majority),
+            "downgrade" => Ok(MergePolicy::Downgrade),
+            "drop" => Ok(MergePolicy::Drop),
+            _ => Err(anyhow!(
+                "unknown merge policy '{s}', expected majority, downgrade or drop"
+            )),
+        }
+    }
+}
+
+/// A node whose replicate observations disagree
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedNode {
+    pub node: NodeId,
+    /// the behavior observed in each replicate, `None` if the node was not observed
+    pub observed: Vec<Option<Behavior>>,
+    /// the behavior in the merged profile, `None` if the node was dropped
+    pub merged: Option<Behavior>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicateMerge {
+    pub policy: MergePolicy,
+    pub replicates: usize,
+    /// number of nodes observed in at least one replicate
+    pub observed_nodes: usize,
+    pub conflicts: Vec<MergedNode>,
+}
+impl ReplicateMerge {
+    pub fn print(&self) {
+        println!("\n## Replicate merging\n");
+        println!("- Replicates: {}", self.replicates);
+        println!("- Observed nodes: {}", self.observed_nodes);
+        println!("- Conflicting nodes: {}", self.conflicts.len());
+        for conflict in &self.conflicts {
+            let observed: Vec<String> = conflict
+                .observed
+                .iter()
+                .map(|b| match b {
+                    Some(b) => b.to_string(),
+                    None => "_".to_string(),
+                })
+                .collect();
+            let merged = match conflict.merged {
+                Some(b) => b.to_string(),
+                None => "dropped".to_string(),
+            };
+            println!(
+                "  - {}: {} -> {merged} ({})",
+                conflict.node,
+                observed.join(" "),
+                conflict.reason
+            );
+        }
+    }
+}
+
+/// Combines replicate observations of the same condition into one profile.
+///
+/// Inputs, MIN and MAX of all replicates are kept.
+/// A node that is observed with the same behavior in all replicates that observe it keeps this behavior,
+/// disagreeing observations are resolved with the `policy`.
+/// A replicate that observes a node with different behaviors is rejected.
+pub fn merge_replicates(
+    replicates: &[Profile],
+    id: &str,
+    policy: MergePolicy,
+) -> Result<(Profile, ReplicateMerge)> {
+    if replicates.iter().any(|r| r.is_time_series()) {
+        bail!("replicates of time series cannot be merged");
+    }
+    let mut merged = Profile::new(id);
+    for replicate in replicates {
+        for (all, nodes) in [
+            (&mut merged.inputs, &replicate.inputs),
+            (&mut merged.min, &replicate.min),
+            (&mut merged.max, &replicate.max),
+        ] {
+            for node in nodes {
+                if !all.contains(node) {
+                    all.push(node.clone());
+                }
+            }
+        }
+    }
+
+    let mut observed: BTreeMap<&NodeId, Vec<Option<Behavior>>> = BTreeMap::new();
+    for (i, replicate) in replicates.iter().enumerate() {
+        for observation in &replicate.observations {
+            let behaviors = observed
+                .entry(&observation.node)
+                .or_insert_with(|| vec![None; replicates.len()]);
+            match behaviors[i] {
+                Some(behavior) if behavior != observation.behavior => bail!(
+                    "replicate {} observes {} as both {behavior} and {}",
+                    i + 1,
+                    observation.node,
+                    observation.behavior
+                ),
+                _ => behaviors[i] = Some(observation.behavior),
+            }
+        }
+    }
+
+    let mut conflicts = vec![];
+    for (node, behaviors) in &observed {
+        let present: Vec<Behavior> = behaviors.iter().flatten().copied().collect();
+        let (behavior, reason) = if present.iter().all(|b| *b == present[0]) {
+            (Some(present[0]), None)
+        } else {
+            let (behavior, reason) = resolve(&present, policy);
+            (behavior, Some(reason))
+        };
+        if let Some(behavior) = behavior {
+            merged.observations.push(Observation {
+                node: (*node).clone(),
+                behavior,
+            });
+        }
+        if let Some(reason) = reason {
+            conflicts.push(MergedNode {
+                node: (*node).clone(),
+                observed: behaviors.clone(),
+                merged: behavior,
+                reason,
+            });
+        }
+    }
+    let report = ReplicateMerge {
+        policy,
+        replicates: replicates.len(),
+        observed_nodes: observed.len(),
+        conflicts,
+    };
+    Ok((merged, report))
+}
+
+/// resolves disagreeing observations, returns the merged behavior and the reason
+fn resolve(behaviors: &[Behavior], policy: MergePolicy) -> (Option<Behavior>, String) {
+    match policy {
+        MergePolicy::Majority => {
+            let mut votes: Vec<(Behavior, usize)> = vec![];
+            for b in behaviors {
+                match votes.iter_mut().find(|(v, _)| v == b) {
+                    Some((_, count)) => *count += 1,
+                    None => votes.push((*b, 1)),
+                }
+            }
+            votes.sort_by_key(|(_, count)| Reverse(*count));
+            if votes.len() > 1 && votes[0].1 == votes[1].1 {
+                (None, format!("tie of {} votes", votes[0].1))
+            } else {
+                let (behavior, count) = votes[0];
+                (
+                    Some(behavior),
+                    format!("majority of {count} of {} votes", behaviors.len()),
+                )
+            }
+        }
+        MergePolicy::Downgrade => {
+            let has = |s: i8| behaviors.iter().any(|b| b.signs().contains(&s));
+            match (has(1), has(-1), has(0)) {
+                (false, _, _) => (
+                    Some(Behavior::NotPlus),
+                    "no replicate increases".to_string(),
+                ),
+                (_, false, _) => (
+                    Some(Behavior::NotMinus),
+                    "no replicate decreases".to_string(),
+                ),
+                (_, _, false) => (Some(Behavior::Change), "all replicates change".to_string()),
+                _ => (None, "replicates allow every behavior".to_string()),
+            }
+        }
+        MergePolicy::Drop => (None, "replicates disagree".to_string()),
+    }
+}
@@ -0,0 +1,312 @@
+use crate::boolean::{to_statements, BoolExpr};
+use crate::cif_parser::{Expression, Graph, SNode, Statement};
+use crate::NodeId;
+use anyhow::{bail, Context, Result};
+use roxmltree::{Document, Node};
+use std::fs::File;
+use std::io::Read;
+
+/// Reads a network in SBML-qual format.
+///
+/// Returns the graph together with warnings about constructs that cannot be represented exactly.
+pub fn read(file: &File) -> Result<(Graph, Vec<String>)> {
+    read_renamed(file, |name| name.to_string())
+}
+
+/// Reads a network in SBML-qual format and renames its nodes, e.g. to resolve aliases.
+///
+/// The nodes are named by the ids of the qualitative species, species without transitions are kept as nodes without edges.
+/// If a transition has function terms, every conjunction of the disjunctive normal form
+/// of its activating terms becomes an AND node.
+/// Inputs that do not occur in the function terms become edges to the outputs,
+/// with the sign `positive` as activation, `negative` as inhibition and `unknown` (or no sign) as unknown influence.
+/// Inputs with unknown sign always become unknown influences.
+pub fn read_renamed(
+    mut file: &File,
+    mut rename: impl FnMut(&str) -> String,
+) -> Result<(Graph, Vec<String>)> {
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    let document = Document::parse(&text).context("invalid XML")?;
+    let model = match document.root_element().children().find(|n| is(n, "model")) {
+        Some(model) => model,
+        None => bail!("no SBML model found"),
+    };
+
+    let mut warnings = vec![];
+    let mut statements = vec![];
+    let mut nodes = vec![];
+    for species in descendants(model, "qualitativeSpecies") {
+        let id = attribute(&species, "id").unwrap_or_default();
+        if !id.is_empty() {
+            nodes.push(id);
+        }
+        if let Some(level) = attribute(&species, "maxLevel") {
+            if level.parse::<u32>().map_or(true, |l| l > 1) {
+                warnings.push(format!(
+                    "species {id}: maximal level {level} is treated as Boolean"
+                ));
+            }
+        }
+    }
+    for (count, transition) in descendants(model, "transition").enumerate() {
+        let id = attribute(&transition, "id")
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| format!("transition {}", count + 1));
+        let outputs: Vec<&str> = descendants(transition, "output")
+            .filter_map(|o| attribute(&o, "qualitativeSpecies"))
+            .collect();
+        if outputs.is_empty() {
+            warnings.push(format!("{id}: transition without output is ignored"));
+            continue;
+        }
+        for output in descendants(transition, "output") {
+            if let Some(effect) = attribute(&output, "transitionEffect") {
+                if effect != "assignmentLevel" {
+                    warnings.push(format!(
+                        "{id}: output effect '{effect}' is treated as assignmentLevel"
+                    ));
+                }
+            }
+        }
+        // each function term is converted separately, so that redundant terms are kept as edges
+        let dnf = transition_function(transition, &id, &mut warnings).map(|terms| {
+            terms
+                .iter()
+                .map(|term| term.to_dnf())
+                .collect::<Option<Vec<_>>>()
+                .map(|dnfs| dnfs.concat())
+        });
+        for output in &outputs {
+            match &dnf {
+                Some(Some(dnf)) => {
+                    statements.extend(to_statements(output, dnf, &mut warnings));
+                    let used: Vec<&str> = dnf.iter().flatten().map(|(n, _)| n.as_str()).collect();
+                    statements.extend(input_statements(
+                        transition,
+                        output,
+                        &used,
+                        &id,
+                        &mut warnings,
+                    ));
+                }
+                Some(None) => {
+                    warnings.push(format!(
+                        "{id}: the function is too large for a normal form, using the signs of the inputs"
+                    ));
+                    statements.extend(input_statements(
+                        transition,
+                        output,
+                        &[],
+                        &id,
+                        &mut warnings,
+                    ));
+                }
+                None => statements.extend(input_statements(
+                    transition,
+                    output,
+                    &[],
+                    &id,
+                    &mut warnings,
+                )),
+            }
+        }
+    }
+    let statements: Vec<Statement> = statements
+        .into_iter()
+        .map(|s| s.renamed(&mut rename))
+        .collect();
+    let mut graph = Graph::from_statements(statements);
+    for node in nodes {
+        graph.add_node(&NodeId::Or(rename(node)));
+    }
+    Ok((graph, warnings))
+}
+
+/// The edges from each input of the transition to the output.
+///
+/// Inputs of species in `used` are skipped unless their sign is unknown,
+/// since function terms cannot express an unknown influence.
+fn input_statements(
+    transition: Node,
+    output: &str,
+    used: &[&str],
+    id: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<Statement> {
+    let mut statements = vec![];
+    for input in descendants(transition, "input") {
+        let species = match attribute(&input, "qualitativeSpecies") {
+            Some(species) => species.to_string(),
+            None => {
+                warnings.push(format!("{id}: input without species is ignored"));
+                continue;
+            }
+        };
+        let sign = attribute(&input, "sign");
+        if used.contains(&species.as_str()) && sign != Some("unknown") {
+            continue;
+        }
+        let expression = match sign {
+            Some("positive") => Expression::Plain(species),
+            Some("negative") => Expression::Negated(species),
+            Some("unknown") | None => Expression::Unknown(species),
+            Some(sign) => {
+                warnings.push(format!(
+                    "{id}: input {species} with sign '{sign}' is treated as unknown influence"
+                ));
+                Expression::Unknown(species)
+            }
+        };
+        statements.push(Statement::new(SNode::Single(expression), output));
+    }
+    statements
+}
+
+/// Returns the conditions for the output to be active, `None` if there are no usable function terms
+fn transition_function(
+    transition: Node,
+    id: &str,
+    warnings: &mut Vec<String>,
+) -> Option<Vec<BoolExpr>> {
+    let default_level = descendants(transition, "defaultTerm")
+        .next()
+        .and_then(|d| attribute(&d, "resultLevel"))
+        .and_then(|l| l.parse::<u32>().ok())
+        .unwrap_or(0);
+    let mut active = vec![];
+    let mut inactive = vec![];
+    for term in descendants(transition, "functionTerm") {
+        let level = attribute(&term, "resultLevel")
+            .and_then(|l| l.parse::<u32>().ok())
+            .unwrap_or(1);
+        let math = match term.children().find(|n| is(n, "math")) {
+            Some(math) => math,
+            None => {
+                warnings.push(format!("{id}: function term without math is ignored"));
+                continue;
+            }
+        };
+        let condition = match math.children().find(|n| n.is_element()) {
+            Some(expression) => match mathml(expression) {
+                Ok(condition) => condition,
+                Err(e) => {
+                    warnings.push(format!("{id}: {e}, using the signs of the inputs"));
+                    return None;
+                }
+            },
+            None => continue,
+        };
+        if level > 0 {
+            active.push(condition);
+        } else {
+            inactive.push(condition);
+        }
+    }
+    match (active.is_empty(), inactive.is_empty()) {
+        (true, true) => None,
+        // the output is active if no term for the inactive level applies
+        (true, false) if default_level > 0 => {
+            Some(vec![BoolExpr::Not(Box::new(BoolExpr::Or(inactive)))])
+        }
+        (true, false) => Some(vec![]),
+        (false, _) => {
+            if default_level > 0 {
+                warnings.push(format!(
+                    "{id}: active default level with terms for the active level, the default term is ignored"
+                ));
+            }
+            Some(active)
+        }
+    }
+}
+
+/// converts a MathML expression into a Boolean expression
+fn mathml(node: Node) -> Result<BoolExpr> {
+    match node.tag_name().name() {
+        "ci" => Ok(BoolExpr::Var(
+            node.text().unwrap_or_default().trim().to_string(),
+        )),
+        "true" => Ok(BoolExpr::Const(true)),
+        "false" => Ok(BoolExpr::Const(false)),
+        "apply" => {
+            let mut children = node.children().filter(|n| n.is_element());
+            let operator = match children.next() {
+                Some(operator) => operator.tag_name().name(),
+                None => bail!("empty apply element"),
+            };
+            let arguments: Vec<Node> = children.collect();
+            match operator {
+                "and" => Ok(BoolExpr::And(
+                    arguments.into_iter().map(mathml).collect::<Result<_>>()?,
+                )),
+                "or" => Ok(BoolExpr::Or(
+                    arguments.into_iter().map(mathml).collect::<Result<_>>()?,
+                )),
+                "not" if arguments.len() == 1 => Ok(BoolExpr::Not(Box::new(mathml(arguments[0])?))),
+                "eq" | "neq" | "lt" | "leq" | "gt" | "geq" if arguments.len() == 2 => {
+                    comparison(operator, arguments[0], arguments[1])
+                }
+                _ => bail!("unsupported MathML operator '{operator}'"),
+            }
+        }
+        name => bail!("unsupported MathML element '{name}'"),
+    }
+}
+
+/// converts the comparison of a species with a level into a literal
+fn comparison(operator: &str, left: Node, right: Node) -> Result<BoolExpr> {
+    let (species, level, operator) = match (left.tag_name().name(), right.tag_name().name()) {
+        ("ci", "cn") => (left, right, operator),
+        // 1 <= x is x >= 1
+        ("cn", "ci") => (
+            right,
+            left,
+            match operator {
+                "lt" => "gt",
+                "leq" => "geq",
+                "gt" => "lt",
+                "geq" => "leq",
+                op => op,
+            },
+        ),
+        _ => bail!("unsupported comparison, expected a species and a level"),
+    };
+    let name = species.text().unwrap_or_default().trim().to_string();
+    let level: i64 = level
+        .text()
+        .unwrap_or_default()
+        .trim()
+        .parse()
+        .context("invalid level")?;
+    // the species is active, i.e. at level 1 or above
+    let active = match (operator, level) {
+        ("eq", 1) | ("geq", 1) | ("gt", 0) | ("neq", 0) => true,
+        ("eq", 0) | ("leq", 0) | ("lt", 1) | ("neq", 1) => false,
+        _ => bail!("unsupported comparison of {name} with level {level}"),
+    };
+    let variable = BoolExpr::Var(name);
+    Ok(if active {
+        variable
+    } else {
+        BoolExpr::Not(Box::new(variable))
+    })
+}
+
+/// elements are matched by their local name, independent of the namespace prefix
+fn is(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+fn descendants<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.descendants().filter(move |n| is(n, name))
+}
+
+fn attribute<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name() == name)
+        .map(|a| a.value())
+}
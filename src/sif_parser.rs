@@ -0,0 +1,129 @@
+use crate::cif_parser::{Expression, Graph, SNode, Statement};
+use crate::NodeId;
+use anyhow::{bail, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Returns true for the names of complex nodes, `and` followed by a number
+pub fn is_complex_node(name: &str) -> bool {
+    name.len() > 3 && name.starts_with("and") && name[3..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// Reads a network in SIF format.
+///
+/// Returns the graph together with warnings about edges that cannot be represented.
+pub fn read(file: &File) -> Result<(Graph, Vec<String>)> {
+    read_renamed(file, |name| name.to_string())
+}
+
+/// Reads a network in SIF format and renames its nodes, e.g. to resolve aliases.
+///
+/// Each line contains a source node, the interaction `1` (activation), `-1` (inhibition)
+/// or `?` (unknown influence) and one or more target nodes, separated by tabs or whitespace.
+/// Complex nodes (see [`is_complex_node`]) become AND nodes of their sources,
+/// in the order of the lines, and their activations become the edges of the AND node.
+/// A line with a single node adds the node without edges.
+pub fn read_renamed(
+    file: &File,
+    mut rename: impl FnMut(&str) -> String,
+) -> Result<(Graph, Vec<String>)> {
+    let mut warnings = vec![];
+    let mut edges = vec![];
+    let mut nodes = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns: Vec<&str> = if line.contains('\t') {
+            line.split('\t').map(|c| c.trim()).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        match columns.as_slice() {
+            // a single node without interactions
+            [node] => {
+                if is_complex_node(node) {
+                    warnings.push(format!("complex node {node} without edges is ignored"));
+                } else {
+                    nodes.push(node.to_string());
+                }
+            }
+            [source, interaction, targets @ ..] if !targets.is_empty() => {
+                let sign = match *interaction {
+                    "1" | "+" => '+',
+                    "-1" | "-" => '-',
+                    "?" | "0" => '?',
+                    _ => {
+                        warnings.push(format!(
+                            "line {}: interaction '{interaction}' is treated as unknown influence",
+                            i + 1
+                        ));
+                        '?'
+                    }
+                };
+                for target in targets {
+                    edges.push((source.to_string(), sign, target.to_string()));
+                }
+            }
+            _ => bail!(
+                "line {}: expected a source, an interaction and a target, found '{line}'",
+                i + 1
+            ),
+        }
+    }
+
+    let expression = |name: &str, sign: char| match sign {
+        '+' => Expression::Plain(name.to_string()),
+        '-' => Expression::Negated(name.to_string()),
+        _ => Expression::Unknown(name.to_string()),
+    };
+    let mut statements = vec![];
+    for (source, sign, target) in &edges {
+        match (is_complex_node(source), is_complex_node(target)) {
+            (false, false) => statements.push(Statement::new(
+                SNode::Single(expression(source, *sign)),
+                target,
+            )),
+            (true, true) => warnings.push(format!(
+                "edge between the complex nodes {source} and {target} is ignored"
+            )),
+            // edges into complex nodes are collected with the edges from the complex node
+            (false, true) => {}
+            (true, false) => {
+                if *sign != '+' {
+                    warnings.push(format!(
+                        "edge from the complex node {source} to {target} is not an activation and is ignored"
+                    ));
+                    continue;
+                }
+                let members: Vec<Expression> = edges
+                    .iter()
+                    .filter(|(s, _, t)| t == source && !is_complex_node(s))
+                    .map(|(s, sign, _)| expression(s, *sign))
+                    .collect();
+                if members.is_empty() {
+                    warnings.push(format!("complex node {source} without sources is ignored"));
+                    continue;
+                }
+                statements.push(Statement::new(SNode::List(members), target));
+            }
+        }
+    }
+    for (_, _, target) in &edges {
+        if is_complex_node(target) && !edges.iter().any(|(s, _, _)| s == target) {
+            warnings.push(format!("complex node {target} without targets is ignored"));
+        }
+    }
+    warnings.dedup();
+    let statements: Vec<Statement> = statements
+        .into_iter()
+        .map(|s| s.renamed(&mut rename))
+        .collect();
+    let mut graph = Graph::from_statements(statements);
+    for node in nodes {
+        graph.add_node(&NodeId::Or(rename(&node)));
+    }
+    Ok((graph, warnings))
+}
@@ -0,0 +1,100 @@
+use crate::profile_parser::Profile;
+use crate::{
+    get_mcos_labelings_per_experiment, get_predictions_per_experiment_under_mcos,
+    get_predictions_per_experiment_under_scenfit, get_scenfit_labelings_per_experiment,
+    ExperimentLabels, Prediction, RepairOp, Setting,
+};
+use anyhow::Result;
+use clingo::FactBase;
+use serde::Serialize;
+
+/// Labels or predictions of the nodes at a time point
+#[derive(Debug, Clone, Serialize)]
+pub struct TimePointLabels {
+    pub time_point: String,
+    pub labels: Vec<Prediction>,
+}
+
+/// A labeling of all time points together with the repairs
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeSeriesLabeling {
+    pub time_points: Vec<TimePointLabels>,
+    pub repairs: Vec<RepairOp>,
+}
+impl TimeSeriesLabeling {
+    pub fn print(&self) {
+        for time_point in &self.time_points {
+            println!("\n  Time point {}:", time_point.time_point);
+            for label in &time_point.labels {
+                println!("  {label}");
+            }
+        }
+        println!("\n  Repair set:");
+        for fix in &self.repairs {
+            println!("  - {fix}");
+        }
+    }
+}
+
+/// returns the labels of the time points of the series, the steps between them are omitted
+fn time_points(series: &Profile, experiments: ExperimentLabels) -> Vec<TimePointLabels> {
+    series
+        .time_points
+        .iter()
+        .map(|t| {
+            let experiment = series.time_point_experiment(t);
+            let labels = experiments
+                .iter()
+                .find(|(e, _)| *e == experiment)
+                .map(|(_, l)| l.clone())
+                .unwrap_or_default();
+            TimePointLabels {
+                time_point: t.name.clone(),
+                labels,
+            }
+        })
+        .collect()
+}
+
+/// Returns at most `number` mcos (or scenfit) labelings of a time series, 0=all.
+///
+/// The labels of a time point describe the changes from the baseline.
+pub fn get_time_series_labelings(
+    graph: &FactBase,
+    series: &Profile,
+    inputs: &FactBase,
+    number: u32,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<Vec<TimeSeriesLabeling>> {
+    let profile = series.to_facts();
+    let labelings: Vec<(ExperimentLabels, Vec<RepairOp>)> = if scenfit {
+        get_scenfit_labelings_per_experiment(graph, &profile, inputs, number, setting)?.collect()
+    } else {
+        get_mcos_labelings_per_experiment(graph, &profile, inputs, number, setting)?.collect()
+    };
+    Ok(labelings
+        .into_iter()
+        .map(|(labels, repairs)| TimeSeriesLabeling {
+            time_points: time_points(series, labels),
+            repairs,
+        })
+        .collect())
+}
+
+/// Returns the predictions under mcos (or scenfit) for each time point of a time series
+pub fn get_time_series_predictions(
+    graph: &FactBase,
+    series: &Profile,
+    inputs: &FactBase,
+    scenfit: bool,
+    setting: &Setting,
+) -> Result<Vec<TimePointLabels>> {
+    let profile = series.to_facts();
+    let predictions = if scenfit {
+        get_predictions_per_experiment_under_scenfit(graph, &profile, inputs, setting)?
+    } else {
+        get_predictions_per_experiment_under_mcos(graph, &profile, inputs, setting)?
+    };
+    Ok(time_points(series, predictions))
+}